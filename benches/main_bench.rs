@@ -1,55 +1,55 @@
 use std::time::Duration;
 
-use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use criterion::{criterion_group, criterion_main, BatchSize, Bencher, BenchmarkId, Criterion};
 
-use fast_fib::{
-    Binet, BinetZ5, Cassini, CassiniGMP, DPIterator, FibFinder, Integer, MatExponentiator,
-    MemoizedRecursor, NaiveRecursor, GMP,
-};
+use fast_fib::{algorithms, find_algorithm, AlgorithmInfo, Complexity};
 
-/// Functions applicable for small numbers: e.g., every algorithm.
-fn small_fns() -> Vec<(Box<dyn Fn(u64) -> Integer>, &'static str)> {
-    vec![
-        (
-            Box::new(|x| NaiveRecursor::default().fib(x)),
-            "Naïve Recursive",
-        ),
-        (
-            Box::new(|x| MemoizedRecursor::default().fib(x)),
-            "Memoized Recursive",
-        ),
-        (Box::new(|x| DPIterator::default().fib(x)), "DP"),
-        (
-            Box::new(|x| MatExponentiator::default().fib(x)),
-            "Matrix Exponentiation",
-        ),
-        (Box::new(|x| Binet::default().fib(x)), "Binet"),
-        (Box::new(|x| BinetZ5::default().fib(x)), "Binet (Z5)"),
-        (
-            Box::new(|x| Cassini::default().fib(x)),
-            "Efficient Matrix Exponentiation",
-        ),
-        (
-            Box::new(|x| CassiniGMP::default().fib(x)),
-            "GMP Algorithm Port",
-        ),
-        (Box::new(|x| GMP::default().fib(x)), "GMP"),
-    ]
+/// Algorithms applicable for small numbers: e.g., every algorithm.
+fn small_algs() -> Vec<AlgorithmInfo> {
+    algorithms()
 }
 
-/// Functions applicable for medium numbers: this excludes the naïve approach.
-fn medium_fns() -> Vec<(Box<dyn Fn(u64) -> Integer>, &'static str)> {
-    small_fns().into_iter().skip(1).collect()
+/// Algorithms applicable for medium numbers: this excludes the exponential-time ones.
+fn medium_algs() -> Vec<AlgorithmInfo> {
+    algorithms()
+        .into_iter()
+        .filter(|alg| alg.complexity > Complexity::Exponential)
+        .collect()
 }
 
-/// Functions applicable for large numbers: this excludes all of the exponential-time algorithms.
-fn large_fns() -> Vec<(Box<dyn Fn(u64) -> Integer>, &'static str)> {
-    small_fns().into_iter().skip(3).collect()
+/// Algorithms applicable for large numbers: this excludes all of the linear-time ones too.
+fn large_algs() -> Vec<AlgorithmInfo> {
+    algorithms()
+        .into_iter()
+        .filter(|alg| alg.complexity == Complexity::Logarithmic)
+        .collect()
 }
 
-/// Functions applicable for the largest numbers: this is only Cassini-based approaches and the Binet-Z5 approach.
-fn largest_fns() -> Vec<(Box<dyn Fn(u64) -> Integer>, &'static str)> {
-    small_fns().into_iter().skip(5).collect()
+/// Algorithms applicable for the largest numbers: this is only the ones that go
+/// past 4 billion, which are the Cassini-based approaches, Binet-Z5, GMP when
+/// it's listed, and Auto.
+fn largest_algs() -> Vec<AlgorithmInfo> {
+    large_algs()
+        .into_iter()
+        .filter(|alg| alg.max_safe_n >= 4_000_000_000)
+        .collect()
+}
+
+/// The algorithms in algs that can handle n, the same way `calibrate` picks
+/// them.
+fn safe_at(algs: Vec<AlgorithmInfo>, n: u64) -> impl Iterator<Item = AlgorithmInfo> {
+    algs.into_iter().filter(move |alg| n <= alg.max_safe_n)
+}
+
+/// Times F(i) with a fresh instance of the algorithm each time, made outside
+/// the timed part so only `fib` is measured. It has to be fresh for
+/// `MemoizedRecursor`, which would otherwise just look up its last answer.
+fn bench_fib(b: &mut Bencher, alg: &AlgorithmInfo, i: u64) {
+    b.iter_batched(
+        || alg.make(),
+        |mut finder| finder.fib(i),
+        BatchSize::SmallInput,
+    )
 }
 
 pub fn fib_small(c: &mut Criterion) {
@@ -60,8 +60,10 @@ pub fn fib_small(c: &mut Criterion) {
         .warm_up_time(Duration::new(1, 0));
     let nums = [2, 3, 5, 10, 15, 25];
     for i in nums {
-        for (f, name) in small_fns() {
-            group.bench_with_input(BenchmarkId::new(name, i), &i, |b, i| b.iter(|| f(*i)));
+        for alg in safe_at(small_algs(), i) {
+            group.bench_with_input(BenchmarkId::new(alg.description, i), &i, |b, i| {
+                bench_fib(b, &alg, *i)
+            });
         }
     }
     group.finish();
@@ -72,11 +74,13 @@ pub fn fib_medium(c: &mut Criterion) {
     group
         .measurement_time(Duration::new(2, 0))
         .warm_up_time(Duration::new(1, 0));
-    // much larger than this, and you stack overflow for memoized
+    // memoized drops out past 1000, before it overflows the stack
     let nums = [50, 200, 800, 1600, 6400, 25600, 52800];
     for i in nums {
-        for (f, name) in medium_fns() {
-            group.bench_with_input(BenchmarkId::new(name, i), &i, |b, i| b.iter(|| f(*i)));
+        for alg in safe_at(medium_algs(), i) {
+            group.bench_with_input(BenchmarkId::new(alg.description, i), &i, |b, i| {
+                bench_fib(b, &alg, *i)
+            });
         }
     }
     group.finish();
@@ -90,8 +94,10 @@ pub fn fib_large(c: &mut Criterion) {
         .warm_up_time(Duration::new(1, 0));
     let nums = [100_000, 400_000, 1_600_000, 6_400_000, 25_600_000];
     for i in nums {
-        for (f, name) in large_fns() {
-            group.bench_with_input(BenchmarkId::new(name, i), &i, |b, i| b.iter(|| f(*i)));
+        for alg in safe_at(large_algs(), i) {
+            group.bench_with_input(BenchmarkId::new(alg.description, i), &i, |b, i| {
+                bench_fib(b, &alg, *i)
+            });
         }
     }
     group.finish();
@@ -105,8 +111,38 @@ pub fn fib_largest(c: &mut Criterion) {
         .warm_up_time(Duration::new(1, 0));
     let nums = [100_000_000, 500_000_000, 1_000_000_000, 4_000_000_000];
     for i in nums {
-        for (f, name) in largest_fns() {
-            group.bench_with_input(BenchmarkId::new(name, i), &i, |b, i| b.iter(|| f(*i)));
+        for alg in safe_at(largest_algs(), i) {
+            group.bench_with_input(BenchmarkId::new(alg.description, i), &i, |b, i| {
+                bench_fib(b, &alg, *i)
+            });
+        }
+    }
+    group.finish();
+}
+
+/// ParallelCassini against the same doubling on one thread, from around where
+/// it starts handing squarings to its helper thread up to the largest sizes.
+pub fn fib_parallel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Fibonacci (parallel)");
+    group
+        .sample_size(10)
+        .measurement_time(Duration::new(2, 0))
+        .warm_up_time(Duration::new(1, 0));
+    let nums = [
+        50_000,
+        100_000,
+        200_000,
+        1_000_000,
+        10_000_000,
+        100_000_000,
+        1_000_000_000,
+    ];
+    for i in nums {
+        for name in ["CassiniGMP", "ParallelCassini"] {
+            let alg = find_algorithm(name).unwrap();
+            group.bench_with_input(BenchmarkId::new(alg.description, i), &i, |b, i| {
+                bench_fib(b, &alg, *i)
+            });
         }
     }
     group.finish();
@@ -116,4 +152,5 @@ criterion_group!(small, fib_small);
 criterion_group!(medium, fib_medium);
 criterion_group!(large, fib_large);
 criterion_group!(largest, fib_largest);
-criterion_main!(small, medium, large, largest);
+criterion_group!(parallel, fib_parallel);
+criterion_main!(small, medium, large, largest, parallel);
@@ -0,0 +1,173 @@
+//! Picks an algorithm based on the index, so callers don't need to study the
+//! benchmarks themselves. Going by `benchmarks.json`, wrapping GMP is the
+//! fastest choice from n = 2 all the way to 4 billion, and its lead is widest
+//! for small n, where the cost is mostly allocating the answer. Below the point
+//! where F(n) stops fitting in a u128 we skip the big integer arithmetic
+//! entirely. For huge n, GMP already switches to FFT multiplication, which is
+//! where almost all of the time goes, so there we spread the squarings over two
+//! threads with `ParallelCassini`.
+
+use std::{io, path::Path};
+
+use crate::{
+    bigint::Integer,
+    calibration::load_tuning,
+    fib_finder::{check_modulus, fib_mod_doubling, FibFinder},
+    gmp::GMP,
+    lucas_finder::LucasFinder,
+    parallel_cassini::ParallelCassini,
+    small::{fib_u128, MAX_U128_INDEX},
+};
+
+/// The indices at which `Auto` switches from one strategy to the next.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct Crossovers {
+    /// The biggest n computed with machine integers. Anything above 186 is
+    /// treated as 186, because F(187) doesn't fit in a u128.
+    pub small_max: u64,
+    /// The smallest n handed to the huge-index strategy.
+    pub huge_min: u64,
+}
+
+impl Default for Crossovers {
+    fn default() -> Self {
+        Self {
+            small_max: MAX_U128_INDEX,
+            huge_min: 1_000_000_000,
+        }
+    }
+}
+
+/// Automatically picks the fastest algorithm for each index.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub struct Auto {
+    crossovers: Crossovers,
+}
+
+/// What `Auto` does for a given index.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub(crate) enum Strategy {
+    Small,
+    Medium,
+    Huge,
+}
+
+impl Auto {
+    /// Uses the given crossover points instead of the defaults.
+    pub fn with_crossovers(crossovers: Crossovers) -> Self {
+        Self { crossovers }
+    }
+
+    /// Uses the crossover points in a tuning file written by `calibrate`.
+    pub fn from_tuning_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::with_crossovers(load_tuning(path)?))
+    }
+
+    /// The crossover points in use.
+    pub fn crossovers(&self) -> Crossovers {
+        self.crossovers
+    }
+
+    pub(crate) fn strategy(&self, n: u64) -> Strategy {
+        if n <= self.crossovers.small_max.min(MAX_U128_INDEX) {
+            Strategy::Small
+        } else if n < self.crossovers.huge_min {
+            Strategy::Medium
+        } else {
+            Strategy::Huge
+        }
+    }
+}
+
+/// Finds F(n) with the given strategy, whether or not it's the one `Auto`
+/// would pick. The small strategy only works up to `MAX_U128_INDEX`.
+pub(crate) fn fib_with(strategy: Strategy, n: u64) -> Integer {
+    match strategy {
+        Strategy::Small => fib_u128(n).unwrap().into(),
+        Strategy::Medium => GMP::default().fib(n),
+        Strategy::Huge => ParallelCassini::default().fib(n),
+    }
+}
+
+impl FibFinder for Auto {
+    fn fib(&mut self, n: u64) -> Integer {
+        fib_with(self.strategy(n), n)
+    }
+
+    fn fib_pair(&mut self, n: u64) -> (Integer, Integer) {
+        match self.strategy(n.saturating_add(1)) {
+            Strategy::Small => (fib_u128(n).unwrap().into(), fib_u128(n + 1).unwrap().into()),
+            Strategy::Medium => GMP::default().fib_pair(n),
+            Strategy::Huge => ParallelCassini::default().fib_pair(n),
+        }
+    }
+
+    fn fib_mod(&mut self, n: u64, m: &Integer) -> Integer {
+        check_modulus(m);
+        match self.strategy(n) {
+            Strategy::Small => Integer::from(fib_u128(n).unwrap()) % m,
+            // reducing as we go beats computing the whole number
+            Strategy::Medium | Strategy::Huge => fib_mod_doubling(&Integer::from(n), m),
+        }
+    }
+}
+
+impl LucasFinder for Auto {
+    fn fib_lucas_pair(&mut self, n: u64) -> (Integer, Integer) {
+        GMP::default().fib_lucas_pair(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cassini;
+
+    #[test]
+    fn test_fib() {
+        let mut alg = Auto::default();
+        let mut reference = Cassini::default();
+        for n in (0..300).chain([1000, 1001, 10000, 100_000]) {
+            assert_eq!(alg.fib(n), reference.fib(n));
+        }
+    }
+
+    #[test]
+    fn test_crossovers() {
+        let crossovers = Crossovers {
+            small_max: 10,
+            huge_min: 100,
+        };
+        let mut alg = Auto::with_crossovers(crossovers);
+        assert_eq!(alg.crossovers(), crossovers);
+        assert_eq!(alg.strategy(10), Strategy::Small);
+        assert_eq!(alg.strategy(11), Strategy::Medium);
+        assert_eq!(alg.strategy(100), Strategy::Huge);
+
+        let mut reference = Cassini::default();
+        for n in 0..200 {
+            assert_eq!(alg.fib(n), reference.fib(n));
+        }
+
+        // the small strategy can't go past what fits in a u128
+        let alg = Auto::with_crossovers(Crossovers {
+            small_max: 1000,
+            huge_min: 2000,
+        });
+        assert_eq!(alg.strategy(MAX_U128_INDEX), Strategy::Small);
+        assert_eq!(alg.strategy(MAX_U128_INDEX + 1), Strategy::Medium);
+    }
+
+    #[test]
+    fn test_other_methods() {
+        let mut alg = Auto::default();
+        let mut reference = Cassini::default();
+        let m = Integer::from(1_000_000_007);
+        for n in [0, 1, 2, 100, 185, 186, 187, 1000, 10000] {
+            assert_eq!(alg.fib_pair(n), reference.fib_pair(n));
+            assert_eq!(alg.fib_mod(n, &m), reference.fib_mod(n, &m));
+            assert_eq!(alg.fib_lucas_pair(n), reference.fib_lucas_pair(n));
+        }
+        assert_eq!(alg.try_fib(100), Ok(reference.fib(100)));
+    }
+}
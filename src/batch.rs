@@ -0,0 +1,97 @@
+//! Finds the Fibonacci numbers at many arbitrary indices at once. Doubling
+//! walks the bits of the index from the top, so the numbers it passes through
+//! on the way to F(n) are the ones at the prefixes of n in binary. Going
+//! through the indices in sorted order, neighbors tend to share long prefixes,
+//! so we keep the chain of the last index around and only redo the part past
+//! where the two differ.
+
+use crate::bigint::{Complete, Integer};
+
+/// Finds F(n) for every n in indices, in the same order.
+pub fn fib_batch(indices: &[u64]) -> Vec<Integer> {
+    let mut order: Vec<usize> = (0..indices.len()).collect();
+    order.sort_unstable_by_key(|&i| indices[i]);
+
+    let mut out = vec![Integer::new(); indices.len()];
+    // the prefixes of the last index, starting from the leading 1, with F(p)
+    // and F(p + 1) for each prefix p
+    let mut chain: Vec<(u64, Integer, Integer)> = Vec::new();
+    for i in order {
+        let n = indices[i];
+        if n == 0 {
+            continue;
+        }
+
+        let bits = 64 - n.leading_zeros();
+        let prefix = |depth: usize| n >> (bits as usize - 1 - depth);
+
+        // keep the part of the chain this index shares
+        let shared = chain
+            .iter()
+            .enumerate()
+            .take_while(|(depth, (p, _, _))| *depth < bits as usize && *p == prefix(*depth))
+            .count();
+        chain.truncate(shared);
+        if chain.is_empty() {
+            chain.push((1, Integer::from(1), Integer::from(1)));
+        }
+
+        for depth in chain.len()..bits as usize {
+            let (_, f_k, f_kp1) = chain.last().unwrap();
+            // F(2k) = F(k) (2 F(k + 1) - F(k)), F(2k + 1) = F(k)^2 + F(k + 1)^2
+            let f_2k = f_k * ((f_kp1 << 1u32).complete() - f_k);
+            let f_2kp1 = f_k.square_ref().complete() + f_kp1.square_ref().complete();
+            let p = prefix(depth);
+            if p % 2 == 0 {
+                chain.push((p, f_2k, f_2kp1));
+            } else {
+                let f_2kp2 = (&f_2k + &f_2kp1).complete();
+                chain.push((p, f_2kp1, f_2kp2));
+            }
+        }
+
+        out[i] = chain.last().unwrap().1.clone();
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cassini, FibFinder};
+
+    #[test]
+    fn test_fib_batch() {
+        let mut reference = Cassini::default();
+        let indices = [
+            1000,
+            0,
+            1,
+            2,
+            1001,
+            5,
+            1000,
+            64,
+            63,
+            65,
+            10000,
+            3,
+            1 << 20,
+            999,
+            4,
+        ];
+        let batch = fib_batch(&indices);
+        assert_eq!(batch.len(), indices.len());
+        for (n, f_n) in indices.iter().zip(batch) {
+            assert_eq!(f_n, reference.fib(*n));
+        }
+
+        assert_eq!(fib_batch(&[]), Vec::<Integer>::new());
+
+        let all: Vec<u64> = (0..300).rev().collect();
+        for (n, f_n) in all.iter().zip(fib_batch(&all)) {
+            assert_eq!(f_n, reference.fib(*n));
+        }
+    }
+}
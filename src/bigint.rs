@@ -0,0 +1,20 @@
+//! The big integer type everything is built on. By default that's `rug`'s,
+//! which wraps GMP. GMP needs a C toolchain and doesn't build everywhere (WASM,
+//! for one), so with the `pure-rust` feature and without `rug` we use a
+//! stand-in with the same interface, built on `num-bigint`. The algorithms only
+//! ever name the type through here, so they work the same with either one.
+
+#[cfg(not(any(feature = "rug", feature = "pure-rust")))]
+compile_error!("either the rug or the pure-rust feature must be enabled");
+
+#[cfg(feature = "rug")]
+pub use rug::{ops, Assign, Complete, Integer};
+
+#[cfg(not(feature = "rug"))]
+pub use crate::pure_rust::{ops, Assign, Complete, Integer};
+
+/// Whether the tests check the biggest indices, F(10^7) and up. GMP gets
+/// through them in seconds, but num-bigint takes minutes, so they only run with
+/// rug.
+#[cfg(test)]
+pub(crate) const TEST_HUGE_INDICES: bool = cfg!(feature = "rug");
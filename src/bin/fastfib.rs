@@ -0,0 +1,425 @@
+//! Command-line tool that computes a Fibonacci number with one of the
+//! algorithms in the library and prints it.
+
+use std::{
+    env,
+    fs::File,
+    io::{self, BufWriter, Write},
+    process,
+    time::Duration,
+};
+
+use fast_fib::{algorithms, calibrate, find_algorithm, Auto, FibFinder, Integer, DEFAULT_POINTS};
+
+const USAGE: &str = "\
+Usage: fastfib [OPTIONS] <N>
+       fastfib calibrate [CALIBRATE OPTIONS]
+
+Computes the Nth Fibonacci number. N may be negative, and may be as big as you
+like with --mod.
+
+Options:
+  -a, --algorithm <NAME>  the algorithm to use, ignoring case: Auto by
+                          default, see --list for the rest
+  -f, --format <FORMAT>   dec (default), hex, digits for the number of decimal
+                          digits, or bits for the number of bits
+  -m, --mod <M>           compute F(N) mod M instead
+  -o, --output <FILE>     write to FILE instead of stdout
+  -t, --tuning <FILE>     use Auto with the crossovers in a tuning file
+  -l, --list              list the algorithms
+  -h, --help              print this message
+
+The calibrate subcommand times the algorithms on this machine and writes a
+tuning file for --tuning.
+
+Calibrate options:
+  --max-n <N>             only time indices up to N, 25600000 by default
+  --time <MS>             milliseconds to spend on each measurement, 100 by
+                          default
+  -o, --output <FILE>     write to FILE instead of stdout";
+
+/// How to print the answer.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Format {
+    Decimal,
+    Hex,
+    Digits,
+    Bits,
+}
+
+/// What the command line asks for.
+enum Command {
+    Help,
+    List,
+    Compute(Args),
+    Calibrate(CalibrateArgs),
+}
+
+/// The parsed command line for computing a number.
+struct Args {
+    n: Integer,
+    algorithm: Box<dyn FibFinder>,
+    format: Format,
+    modulus: Option<Integer>,
+    output: Option<String>,
+}
+
+/// The parsed command line for the calibrate subcommand.
+struct CalibrateArgs {
+    max_n: u64,
+    time_per_point: Duration,
+    output: Option<String>,
+}
+
+/// Prints a table of the algorithms and what they're good for.
+fn list_algorithms() {
+    // the header lines up with the widths below
+    println!("NAME              COMPLEXITY        MAX SAFE N  EXACT  RECURSIVE");
+    for alg in algorithms() {
+        println!(
+            "{:<18}{:<14}{:>14}  {:<7}{}",
+            alg.name,
+            format!("{:?}", alg.complexity),
+            alg.max_safe_n,
+            if alg.exact { "yes" } else { "no" },
+            if alg.recursive { "yes" } else { "no" },
+        );
+    }
+}
+
+/// Parses the arguments after the program name.
+fn parse_command(args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.peekable();
+    if args.peek().map(String::as_str) == Some("calibrate") {
+        args.next();
+        return parse_calibrate_args(args);
+    }
+
+    parse_args(args)
+}
+
+/// Parses the arguments for computing a number.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut n = None;
+    let mut algorithm_name = "Auto".to_owned();
+    let mut format = Format::Decimal;
+    let mut modulus = None;
+    let mut output = None;
+    let mut tuning = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().ok_or_else(|| format!("{} needs a value", flag));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-l" | "--list" => return Ok(Command::List),
+            "-a" | "--algorithm" => algorithm_name = value(&arg)?,
+            "-f" | "--format" => {
+                format = match value(&arg)?.as_str() {
+                    "dec" => Format::Decimal,
+                    "hex" => Format::Hex,
+                    "digits" => Format::Digits,
+                    "bits" => Format::Bits,
+                    other => return Err(format!("unknown format {}", other)),
+                }
+            }
+            "-m" | "--mod" => {
+                let m = value(&arg)?;
+                let m = m
+                    .parse::<Integer>()
+                    .map_err(|_| format!("modulus {} is not an integer", m))?;
+                if m <= 0 {
+                    return Err("modulus must be positive".to_owned());
+                }
+                modulus = Some(m);
+            }
+            "-o" | "--output" => output = Some(value(&arg)?),
+            "-t" | "--tuning" => tuning = Some(value(&arg)?),
+            // negative values of N don't look like options, but allow the usual
+            // separator anyway
+            "--" => {}
+            _ if n.is_none() => {
+                n = Some(
+                    arg.parse::<Integer>()
+                        .map_err(|_| format!("N must be an integer, not {}", arg))?,
+                );
+            }
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+
+    let algorithm: Box<dyn FibFinder> = match tuning {
+        Some(path) if algorithm_name.eq_ignore_ascii_case("Auto") => Box::new(
+            Auto::from_tuning_file(&path)
+                .map_err(|e| format!("can't load tuning file {}: {}", path, e))?,
+        ),
+        Some(_) => return Err("--tuning only works with Auto".to_owned()),
+        None => find_algorithm(&algorithm_name)
+            .ok_or_else(|| format!("unknown algorithm {}", algorithm_name))?
+            .make(),
+    };
+
+    Ok(Command::Compute(Args {
+        n: n.ok_or("missing N")?,
+        algorithm,
+        format,
+        modulus,
+        output,
+    }))
+}
+
+/// Computes the number the arguments ask for.
+fn compute(args: &mut Args) -> Result<Integer, String> {
+    let alg = &mut args.algorithm;
+    match &args.modulus {
+        Some(m) if args.n < 0 => {
+            // F(-n) mod m is F(n) or -F(n) mod m, depending on whether n is even
+            let abs_n = Integer::from(args.n.abs_ref());
+            let f_n = alg.fib_mod_big(&abs_n, m);
+            if abs_n.is_even() && f_n != 0 {
+                Ok(m - f_n)
+            } else {
+                Ok(f_n)
+            }
+        }
+        Some(m) => Ok(alg.fib_mod_big(&args.n, m)),
+        None => {
+            if let Some(n) = args.n.to_u64() {
+                alg.try_fib(n).map_err(|e| e.to_string())
+            } else if let Some(n) = args.n.to_i64() {
+                // F(-n) = (-1)^(n + 1) F(n)
+                let f_n = alg.try_fib(n.unsigned_abs()).map_err(|e| e.to_string())?;
+                Ok(if n % 2 == 0 { -f_n } else { f_n })
+            } else {
+                Err(format!("N = {} is too big without --mod", args.n))
+            }
+        }
+    }
+}
+
+/// Writes out F(n) in the given format.
+fn format_number(f_n: &Integer, format: Format) -> String {
+    match format {
+        Format::Decimal => f_n.to_string(),
+        Format::Hex => f_n.to_string_radix(16),
+        Format::Digits => {
+            let digits = f_n.to_string().trim_start_matches('-').len();
+            digits.to_string()
+        }
+        Format::Bits => f_n.significant_bits().to_string(),
+    }
+}
+
+/// Writes text to the given file, or stdout if there isn't one.
+fn write_output(text: &str, output: &Option<String>) -> Result<(), String> {
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).map_err(|e| format!("can't create {}: {}", path, e))?,
+        )),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+    write!(out, "{}", text)
+        .and_then(|_| out.flush())
+        .map_err(|e| format!("can't write the output: {}", e))
+}
+
+/// Parses the arguments of the calibrate subcommand.
+fn parse_calibrate_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut max_n = 25_600_000;
+    let mut time_per_point = Duration::from_millis(100);
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().ok_or_else(|| format!("{} needs a value", flag));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--max-n" => {
+                let n = value(&arg)?;
+                max_n = n
+                    .parse()
+                    .map_err(|_| format!("--max-n must be a whole number, not {}", n))?;
+            }
+            "--time" => {
+                let ms = value(&arg)?;
+                time_per_point = Duration::from_millis(
+                    ms.parse()
+                        .map_err(|_| format!("--time must be a whole number, not {}", ms))?,
+                );
+            }
+            "-o" | "--output" => output = Some(value(&arg)?),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+
+    Ok(Command::Calibrate(CalibrateArgs {
+        max_n,
+        time_per_point,
+        output,
+    }))
+}
+
+/// Runs the calibrate subcommand.
+fn run_calibrate(args: &CalibrateArgs) -> Result<(), String> {
+    let points: Vec<u64> = DEFAULT_POINTS
+        .iter()
+        .copied()
+        .filter(|&n| n <= args.max_n)
+        .collect();
+    let calibration = calibrate(&points, args.time_per_point);
+    write_output(&calibration.to_json(), &args.output)
+}
+
+fn run() -> Result<(), String> {
+    match parse_command(env::args().skip(1))? {
+        Command::Help => println!("{}", USAGE),
+        Command::List => list_algorithms(),
+        Command::Calibrate(args) => run_calibrate(&args)?,
+        Command::Compute(mut args) => {
+            let f_n = compute(&mut args)?;
+            write_output(&(format_number(&f_n, args.format) + "\n"), &args.output)?;
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("fastfib: {}", e);
+        eprintln!("Try 'fastfib --help' for more information.");
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fast_fib::{Cassini, FibError};
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_command(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn parse_compute(args: &[&str]) -> Args {
+        match parse(args) {
+            Ok(Command::Compute(args)) => args,
+            _ => panic!("{:?} doesn't ask for a number", args),
+        }
+    }
+
+    fn run_compute(args: &[&str]) -> Result<Integer, String> {
+        compute(&mut parse_compute(args))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = parse_compute(&["100"]);
+        assert_eq!(args.n, 100);
+        assert_eq!(args.format, Format::Decimal);
+        assert_eq!(args.modulus, None);
+        assert_eq!(args.output, None);
+
+        let args = parse_compute(&["-a", "cassini", "--format", "hex", "-o", "out.txt", "12"]);
+        assert_eq!(args.n, 12);
+        assert_eq!(args.format, Format::Hex);
+        assert_eq!(args.output.as_deref(), Some("out.txt"));
+
+        let args = parse_compute(&["--mod", "1000", "-f", "bits", "--", "-7"]);
+        assert_eq!(args.n, -7);
+        assert_eq!(args.format, Format::Bits);
+        assert_eq!(args.modulus, Some(Integer::from(1000)));
+
+        assert!(matches!(parse(&["--list"]), Ok(Command::List)));
+        assert!(matches!(parse(&["-h"]), Ok(Command::Help)));
+        assert!(matches!(parse(&["5", "--help"]), Ok(Command::Help)));
+    }
+
+    #[test]
+    fn test_parse_calibrate() {
+        match parse(&[
+            "calibrate",
+            "--max-n",
+            "1000",
+            "--time",
+            "5",
+            "-o",
+            "tuning.json",
+        ]) {
+            Ok(Command::Calibrate(args)) => {
+                assert_eq!(args.max_n, 1000);
+                assert_eq!(args.time_per_point, Duration::from_millis(5));
+                assert_eq!(args.output.as_deref(), Some("tuning.json"));
+            }
+            _ => panic!("calibrate didn't parse"),
+        }
+        assert!(matches!(parse(&["calibrate", "--help"]), Ok(Command::Help)));
+
+        let err = |args: &[&str]| parse(args).err().unwrap();
+        assert_eq!(
+            err(&["calibrate", "--max-n", "-1"]),
+            "--max-n must be a whole number, not -1"
+        );
+        assert_eq!(err(&["calibrate", "--time"]), "--time needs a value");
+        assert_eq!(err(&["calibrate", "100"]), "unexpected argument 100");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |args: &[&str]| parse(args).err().unwrap();
+        assert_eq!(err(&[]), "missing N");
+        assert_eq!(err(&["five"]), "N must be an integer, not five");
+        assert_eq!(err(&["1", "2"]), "unexpected argument 2");
+        assert_eq!(err(&["--mod"]), "--mod needs a value");
+        assert_eq!(err(&["--mod", "ten", "5"]), "modulus ten is not an integer");
+        assert_eq!(err(&["--mod", "0", "5"]), "modulus must be positive");
+        assert_eq!(err(&["-f", "oct", "5"]), "unknown format oct");
+        assert_eq!(err(&["-a", "Slow", "5"]), "unknown algorithm Slow");
+        assert_eq!(
+            err(&["-a", "GMP", "-t", "tuning.json", "5"]),
+            "--tuning only works with Auto"
+        );
+        assert!(err(&["-t", "/nonexistent/tuning.json", "5"]).starts_with("can't load tuning file"));
+    }
+
+    #[test]
+    fn test_compute() {
+        assert_eq!(run_compute(&["10"]), Ok(Integer::from(55)));
+        assert_eq!(run_compute(&["-10"]), Ok(Integer::from(-55)));
+        assert_eq!(run_compute(&["--", "-9"]), Ok(Integer::from(34)));
+        assert_eq!(
+            run_compute(&["--mod", "1000", "-10"]),
+            Ok(Integer::from(945))
+        );
+        assert_eq!(run_compute(&["--mod", "1000", "-9"]), Ok(Integer::from(34)));
+
+        assert_eq!(
+            run_compute(&["-a", "NaiveRecursor", "50"]),
+            Err(FibError::UnsupportedRange { n: 50, max: 40 }.to_string())
+        );
+        assert_eq!(
+            run_compute(&["100000000000000000000"]),
+            Err("N = 100000000000000000000 is too big without --mod".to_owned())
+        );
+
+        // every algorithm takes indices and moduli past u64 with --mod
+        let n = "18446744073709551617";
+        let m = "18446744073709551629";
+        let expected = Cassini::default().fib_mod_big(&n.parse().unwrap(), &m.parse().unwrap());
+        for alg in algorithms() {
+            assert_eq!(
+                run_compute(&["-a", alg.name, "--mod", m, n]),
+                Ok(expected.clone())
+            );
+            assert!(run_compute(&["-a", alg.name, "1000000000000"]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_format_number() {
+        let f_n = Integer::from(144);
+        assert_eq!(format_number(&f_n, Format::Decimal), "144");
+        assert_eq!(format_number(&f_n, Format::Hex), "90");
+        assert_eq!(format_number(&f_n, Format::Digits), "3");
+        assert_eq!(format_number(&f_n, Format::Bits), "8");
+        assert_eq!(format_number(&Integer::from(-55), Format::Digits), "2");
+    }
+}
@@ -3,9 +3,17 @@
 //! numbers, so depending on the given precision errors will start accumulating
 //! after a while.
 
+use std::convert::TryFrom;
+
 use rug::{ops::Pow, Float, Integer};
 
-use crate::{fib_finder::FibFinder, repeated_squaring::power};
+use crate::{
+    cassini::Cassini,
+    error::FibError,
+    fib_finder::{check_size, FibFinder},
+    repeated_squaring::power,
+    size::fib_bit_length,
+};
 
 /// Approach using Binet's formula.
 #[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
@@ -13,23 +21,105 @@ pub struct Binet {}
 
 impl FibFinder for Binet {
     fn fib(&mut self, n: u64) -> Integer {
-        // using 1% more than necessary as a buffer
-        let prec: u32 = (1.05 * (n as f64) * Float::with_val(10, 1.618).log2())
-            .ceil()
-            .to_integer()
-            .unwrap()
-            .max(Integer::from(53))
-            .to_u32()
-            .unwrap()
-            + 10;
-        let one_half = Float::with_val(prec, 0.5);
-        let sqrt5 = Float::with_val(prec, 5).sqrt();
-        let phi = Float::with_val(prec, &one_half + &one_half * &sqrt5);
-        let ans = power(phi, n, Float::with_val(prec, 1)) / sqrt5;
-        ans.to_integer().unwrap()
+        match precision(n) {
+            Some(prec) => fib_with_precision(n, prec),
+            None => panic!("{}", FibError::PrecisionOverflow { n }),
+        }
+    }
+
+    fn try_fib(&mut self, n: u64) -> Result<Integer, FibError> {
+        check_size(n)?;
+        let prec = precision(n).ok_or(FibError::PrecisionOverflow { n })?;
+        if n > Self::MAX_INDEX {
+            return Err(FibError::UnsupportedRange {
+                n,
+                max: Self::MAX_INDEX,
+            });
+        }
+        Ok(fib_with_precision(n, prec))
+    }
+}
+
+impl Binet {
+    /// The biggest index `try_fib` takes. Every bit of F(n) has to be carried
+    /// through the floating-point powers, which are several times slower than
+    /// the same squarings on integers, so past this it's no longer worth it.
+    pub const MAX_INDEX: u64 = 25_600_000;
+
+    /// Finds the first k decimal digits of F(n), or all of them if there are
+    /// fewer than k. Only the fractional part of log10 F(n) matters for that,
+    /// and F(n) is within a hair of phi^n / sqrt 5, so this works out
+    /// n log10(phi) - log10(sqrt 5) with about as many bits as k digits and n
+    /// need, instead of enough for all of F(n). That keeps it quick for n up to
+    /// 10^18 and beyond.
+    pub fn leading_digits(&mut self, n: u64, k: u32) -> Integer {
+        assert!(k > 0, "need at least one digit");
+        // F(n) has about n log10(phi) digits. When that's not many more than k,
+        // finding F(n) is cheap and avoids worrying about psi^n.
+        if n as f64 * 0.208_987_640_249_978_73 <= f64::from(k) + 30.0 {
+            let f_n = Cassini::default().fib(n);
+            let digits = f_n.to_string().len() as u32;
+            return f_n / Integer::from(10).pow(digits.saturating_sub(k));
+        }
+
+        let mut guard = 64;
+        loop {
+            match leading_digits_with_guard(n, k, guard) {
+                Some(digits) => return digits,
+                None => guard *= 2,
+            }
+        }
+    }
+}
+
+/// Finds the first k digits of phi^n / sqrt 5 with guard bits past what k and
+/// n need, unless the answer is too close to a boundary between two k-digit
+/// prefixes to tell which side it's on. For the n that get here, F(n) has at
+/// least k + 30 digits, so it can't be a multiple of 10^30 and the psi^n term
+/// can never move it across a boundary.
+fn leading_digits_with_guard(n: u64, k: u32, guard: u32) -> Option<Integer> {
+    let prec = ((f64::from(k) + (n as f64).log10() + 2.0) * std::f64::consts::LOG2_10).ceil()
+        as u32
+        + guard;
+    let sqrt5 = Float::with_val(prec, 5).sqrt();
+    let log_phi = (Float::with_val(prec, &sqrt5 + 1u32) / 2u32).log10();
+    let log_f_n = log_phi * Integer::from(n) - sqrt5.log10();
+
+    // 10^(k - 1 + frac(log10 F(n))) has the digits we want before the point
+    let prefix = (log_f_n.fract() + (k - 1)).exp10();
+    let digits = prefix.clone().floor();
+    let above = Float::with_val(prec, &prefix - &digits);
+    let below = Float::with_val(prec, 1u32 - above.clone());
+    let margin = 0.5f64.powi(guard as i32 / 2);
+    if above.to_f64() > margin && below.to_f64() > margin {
+        digits.to_integer()
+    } else {
+        None
+    }
+}
+
+/// The precision needed to get F(n) exactly, if a Float can have that much.
+fn precision(n: u64) -> Option<u32> {
+    // every bit of F(n), plus room for the rounding error, which repeated
+    // squaring grows by about a bit for every bit of n
+    let index_bits = u64::from(64 - n.leading_zeros());
+    let prec = u32::try_from(fib_bit_length(n) + 2 * index_bits + 16).ok()?;
+    if prec <= rug::float::prec_max() {
+        Some(prec.max(53))
+    } else {
+        None
     }
 }
 
+/// Computes F(n) using floats with the given precision.
+fn fib_with_precision(n: u64, prec: u32) -> Integer {
+    let one_half = Float::with_val(prec, 0.5);
+    let sqrt5 = Float::with_val(prec, 5).sqrt();
+    let phi = Float::with_val(prec, &one_half + &one_half * &sqrt5);
+    let ans = power(phi, n, Float::with_val(prec, 1)) / sqrt5;
+    ans.to_integer().unwrap()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,4 +154,49 @@ mod tests {
             3428746875_u64
         );
     }
+
+    #[test]
+    fn test_leading_digits() {
+        let mut alg = Binet::default();
+        let mut reference = Cassini::default();
+        for n in [0, 1, 2, 12, 100, 200, 1000, 1001, 10000, 123_456] {
+            let f_n = reference.fib(n).to_string();
+            for k in [1, 5, 20, 100] {
+                let prefix = &f_n[..f_n.len().min(k)];
+                assert_eq!(alg.leading_digits(n, k as u32).to_string(), prefix);
+            }
+        }
+
+        // checked against Python's decimal module with 200 digits
+        let huge = [
+            (1_000_000_000_000_001, "391987222047046157888683082538"),
+            (1_000_000_000_000_000_000, "262897881867922046740750648916"),
+            (u64::MAX, "690702890954969422368993769184"),
+        ];
+        for (n, prefix) in huge {
+            assert_eq!(alg.leading_digits(n, 30).to_string(), prefix);
+            assert_eq!(alg.leading_digits(n, 3).to_string(), prefix[..3]);
+        }
+    }
+
+    #[test]
+    fn test_try_fib() {
+        let mut alg = Binet::default();
+        assert_eq!(alg.try_fib(1000), Ok(alg.fib(1000)));
+        assert_eq!(
+            alg.try_fib(30_000_000),
+            Err(FibError::UnsupportedRange {
+                n: 30_000_000,
+                max: Binet::MAX_INDEX
+            })
+        );
+        assert_eq!(
+            alg.try_fib(10_000_000_000),
+            Err(FibError::PrecisionOverflow { n: 10_000_000_000 })
+        );
+        assert!(matches!(
+            alg.try_fib(u64::MAX),
+            Err(FibError::ResourceLimit { .. })
+        ));
+    }
 }
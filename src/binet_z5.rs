@@ -4,11 +4,16 @@
 
 use std::{
     fmt::Display,
-    ops::{Mul, MulAssign},
+    ops::{Add, Mul, MulAssign, Sub},
 };
 
-use crate::{repeated_squaring::power, FibFinder};
-use rug::{Assign, Complete, Integer};
+use crate::{
+    bigint::{ops::RemRounding, Assign, Complete, Integer},
+    fib_finder::check_modulus,
+    repeated_squaring::{power, power_integer},
+    ring::{by_value, Ring},
+    FibFinder, LucasFinder,
+};
 
 /// A number of the form a/2 + b/2 sqrt 5, with a and b integers.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -79,6 +84,234 @@ impl Z5 {
     pub fn one() -> Z5 {
         Z5::new(2, 0)
     }
+
+    /// The multiplicative inverse, if there is one in Z(root 5): that is, if
+    /// the norm is 1 or -1, like it is for phi.
+    pub fn inverse(&self) -> Option<Z5> {
+        // the norm is x times its conjugate, (a^2 - 5b^2) / 4, so the inverse
+        // is the conjugate divided by the norm
+        let norm = (self.a.square_ref().complete() - self.b.square_ref().complete() * 5u8) >> 2u32;
+        if norm == 1 {
+            Some(Z5 {
+                a: self.a.clone(),
+                b: Integer::from(-&self.b),
+            })
+        } else if norm == -1 {
+            Some(Z5 {
+                a: Integer::from(-&self.a),
+                b: self.b.clone(),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// A number of the form a + b sqrt 5, with a and b in any ring. `Z5` without
+/// the halves, which need 2 to be invertible: over `Zmod<M>` this is
+/// Z(root 5)/MZ, and for primes p where 5 isn't a square mod p it's the field
+/// with p^2 elements.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Zsqrt5<T = Integer> {
+    /// The rational part.
+    pub a: T,
+    /// The coefficient of sqrt 5.
+    pub b: T,
+}
+
+impl<T: Ring> Zsqrt5<T> {
+    /// Constructor
+    pub fn new(a: T, b: T) -> Self {
+        Zsqrt5 { a, b }
+    }
+
+    /// sqrt 5 itself.
+    pub fn sqrt5() -> Self {
+        Zsqrt5::new(T::zero(), T::one())
+    }
+
+    /// a - b sqrt 5.
+    pub fn conjugate(&self) -> Self {
+        Zsqrt5::new(self.a.clone(), T::zero() - &self.b)
+    }
+
+    /// The number times its conjugate, a^2 - 5b^2, which is invertible exactly
+    /// when the number is.
+    pub fn norm(&self) -> T {
+        self.a.square() - T::from_small(5) * &self.b.square()
+    }
+}
+
+impl<T: Ring> Add<&Zsqrt5<T>> for Zsqrt5<T> {
+    type Output = Self;
+
+    fn add(self, rhs: &Self) -> Self {
+        Zsqrt5::new(self.a + &rhs.a, self.b + &rhs.b)
+    }
+}
+
+impl<T: Ring> Sub<&Zsqrt5<T>> for Zsqrt5<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: &Self) -> Self {
+        Zsqrt5::new(self.a - &rhs.a, self.b - &rhs.b)
+    }
+}
+
+impl<T: Ring> Mul<&Zsqrt5<T>> for Zsqrt5<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: &Self) -> Self {
+        // the same three-multiplication trick as for Z5
+        let (a, b) = (self.a, self.b);
+        let (c, d) = (&rhs.a, &rhs.b);
+        let k1 = c.clone() * &(a.clone() + &b);
+        let k2 = b * &(c.clone() - T::from_small(5) * d);
+        let k3 = a * &(d.clone() - c);
+        Zsqrt5::new(k1.clone() - k2, k1 + k3)
+    }
+}
+
+by_value!(impl<T: Ring> Zsqrt5<T>; Add add, Sub sub, Mul mul);
+
+impl<T: Ring> Ring for Zsqrt5<T> {
+    fn from_small(x: u32) -> Self {
+        Zsqrt5::new(T::from_small(x), T::zero())
+    }
+}
+
+/// A number of the form a/2 + b/2 sqrt 5, with a and b only known modulo
+/// `modulus`. Every multiplication halves a and b, which loses a factor of 2 of
+/// the modulus, so we start with the target modulus times a big enough power of
+/// 2 and let it shrink as we go.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+struct Z5Mod {
+    a: Integer,
+    b: Integer,
+    modulus: Integer,
+}
+
+impl MulAssign for Z5Mod {
+    fn mul_assign(&mut self, rhs: Self) {
+        // same trick as for Z5, but the products are only known modulo the
+        // smaller of the two moduli
+        let (a, b) = (&self.a, &self.b);
+        let (c, d) = (&rhs.a, &rhs.b);
+        let k1 = c * (a + b).complete();
+        let k2 = b * (c - 5u8 * d).complete();
+        let k3 = a * (d - c).complete();
+
+        let modulus = self.modulus.clone().min(rhs.modulus);
+
+        // the true values are even, and because the modulus is even the
+        // remainders are too, so halving them is exact
+        self.a.assign((&k1 - k2).rem_euc(&modulus));
+        self.b.assign((k1 + k3).rem_euc(&modulus));
+        self.a >>= 1;
+        self.b >>= 1;
+        self.modulus = modulus >> 1;
+    }
+}
+
+impl Z5Mod {
+    /// Constructor. `modulus` must be a multiple of 2 raised to at least the
+    /// number of multiplications that will be done.
+    pub fn new(a: u64, b: u64, modulus: Integer) -> Z5Mod {
+        Z5Mod {
+            a: Integer::from(a) % &modulus,
+            b: Integer::from(b) % &modulus,
+            modulus,
+        }
+    }
+}
+
+/// A number of the form a/2 + b/2 sqrt 5 in the field with p^2 elements, for
+/// an odd prime p where 5 isn't a square mod p. Halving is exact mod p, so
+/// unlike `Z5Mod` the modulus stays put.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+struct Z5ModP {
+    a: Integer,
+    b: Integer,
+    p: Integer,
+}
+
+impl MulAssign for Z5ModP {
+    fn mul_assign(&mut self, rhs: Self) {
+        // same trick as for Z5
+        let (a, b) = (&self.a, &self.b);
+        let (c, d) = (&rhs.a, &rhs.b);
+        let k1 = c * (a + b).complete();
+        let k2 = b * (c - 5u8 * d).complete();
+        let k3 = a * (d - c).complete();
+
+        let a = (&k1 - k2).rem_euc(&self.p);
+        let b = (k1 + k3).rem_euc(&self.p);
+        self.a = half_mod(a, &self.p);
+        self.b = half_mod(b, &self.p);
+    }
+}
+
+/// x/2 mod p, for x from 0 to p - 1 and odd p: if x is odd, x + p is even and
+/// the same mod p.
+fn half_mod(x: Integer, p: &Integer) -> Integer {
+    if x.is_odd() {
+        (x + p) >> 1u32
+    } else {
+        x >> 1u32
+    }
+}
+
+/// A square root of 5 mod p, for a prime p that's 1 or 4 mod 5, which by
+/// quadratic reciprocity are the ones where there is one. This is
+/// Tonelli-Shanks.
+fn sqrt5_mod(p: &Integer) -> Integer {
+    let pow_mod = |x: &Integer, e: &Integer| x.clone().pow_mod(e, p).unwrap();
+    let p_minus_1 = Integer::from(p - 1u32);
+
+    // p - 1 = q 2^s with q odd
+    let mut q = p_minus_1.clone();
+    let mut s = 0;
+    while q.is_even() {
+        q >>= 1u32;
+        s += 1;
+    }
+
+    // anything that isn't a square: half of everything but 0 isn't, so this
+    // won't take long if p really is prime
+    let half = Integer::from(&p_minus_1 >> 1u32);
+    let mut z = Integer::from(2);
+    while pow_mod(&z, &half) != p_minus_1 {
+        z += 1;
+        assert!(z < *p, "{} isn't prime", p);
+    }
+
+    // r^2 = 5 t, and each round makes the order of t smaller, until t = 1
+    let five = Integer::from(5);
+    let mut m = s;
+    let mut c = pow_mod(&z, &q);
+    let mut t = pow_mod(&five, &q);
+    let mut r = pow_mod(&five, &(Integer::from(&q + 1u32) >> 1u32));
+    while t != 1 {
+        // t has order 2^i
+        let mut i = 0;
+        let mut t_pow = t.clone();
+        while t_pow != 1 {
+            t_pow = t_pow.square() % p;
+            i += 1;
+            assert!(i < m, "{} isn't prime", p);
+        }
+
+        let mut b = c;
+        for _ in 0..m - i - 1 {
+            b = b.square() % p;
+        }
+        m = i;
+        c = b.square_ref().complete() % p;
+        t = t * &c % p;
+        r = r * &b % p;
+    }
+
+    r
 }
 
 /// Binet approach using Z(root 5) integer field extension.
@@ -99,13 +332,109 @@ impl FibFinder for BinetZ5 {
             }
         }
     }
+
+    fn fib_signed(&mut self, n: i64) -> Integer {
+        // phi^-n works the same way as phi^n, and phi has an inverse because
+        // its norm is -1
+        let phi = Z5::new(1, 1);
+        let base = if n < 0 { phi.inverse().unwrap() } else { phi };
+        power(base, n.unsigned_abs(), Z5::one()).b
+    }
+
+    fn fib_pair(&mut self, n: u64) -> (Integer, Integer) {
+        // phi^n = L(n)/2 + F(n)/2 root 5, and F(n + 1) = (F(n) + L(n)) / 2
+        let ans = power(Z5::new(1, 1), n, Z5::one());
+        let f_np1 = (&ans.a + &ans.b).complete() >> 1u32;
+        (ans.b, f_np1)
+    }
+
+    fn fib_mod(&mut self, n: u64, m: &Integer) -> Integer {
+        check_modulus(m);
+        // repeated squaring does at most two multiplications per bit of n
+        let bits = 64 - n.leading_zeros();
+        let modulus = Integer::from(m << (2 * bits + 1));
+        let ans = power(
+            Z5Mod::new(1, 1, modulus.clone()),
+            n,
+            Z5Mod::new(2, 0, modulus),
+        );
+        ans.b % m
+    }
+
+    fn fib_mod_big(&mut self, n: &Integer, m: &Integer) -> Integer {
+        check_modulus(m);
+        let bits = n.significant_bits();
+        let modulus = Integer::from(m << (2 * bits + 1));
+        let ans = power_integer(
+            Z5Mod::new(1, 1, modulus.clone()),
+            n,
+            Z5Mod::new(2, 0, modulus),
+        );
+        ans.b % m
+    }
+}
+
+impl BinetZ5 {
+    /// Finds F(n) mod p for a prime p straight from Binet's formula,
+    /// F(n) = (phi^n - psi^n) / sqrt 5. When p is 1 or 4 mod 5, sqrt 5 is
+    /// already in the integers mod p; otherwise the formula runs in the field
+    /// with p^2 elements, where it is. None of this shares any code with
+    /// `fib_mod`, so each can check the other.
+    pub fn fib_mod_prime(&mut self, n: u64, p: &Integer) -> Integer {
+        assert!(*p >= 2, "{} isn't prime", p);
+        match (Integer::from(p % 5u32).to_u32().unwrap(), p.to_u32()) {
+            // in characteristic 2 phi and psi are the roots of x^2 + x + 1, so
+            // they're cube roots of 1
+            (_, Some(2)) => Integer::from(u32::from(n % 3 != 0)),
+            // 5 = 0, so phi = psi = 1/2 = 3 is a double root and the formula
+            // turns into F(n) = n phi^(n - 1)
+            (0, _) => match n {
+                0 => 0.into(),
+                _ => Integer::from((n % 5) * power(3, (n - 1) % 4, 1) % 5),
+            },
+            (1, _) | (4, _) => {
+                let sqrt5 = sqrt5_mod(p);
+                let inv = |x: Integer| x.pow_mod(&Integer::from(-1), p).unwrap();
+                let half = Integer::from(p + 1u32) >> 1u32;
+                let phi = (Integer::from(&sqrt5 + 1u32) * &half) % p;
+                let psi = (Integer::from(1u32 - &sqrt5) * half).rem_euc(p);
+                let n = Integer::from(n);
+                let diff = phi.pow_mod(&n, p).unwrap() - psi.pow_mod(&n, p).unwrap();
+                (diff * inv(sqrt5)).rem_euc(p)
+            }
+            _ => {
+                // phi^n = L(n)/2 + F(n)/2 sqrt 5, and psi^n is its conjugate,
+                // so the formula just picks out the sqrt 5 part
+                let phi = Z5ModP {
+                    a: 1.into(),
+                    b: 1.into(),
+                    p: p.clone(),
+                };
+                let one = Z5ModP {
+                    a: 2.into(),
+                    b: 0.into(),
+                    p: p.clone(),
+                };
+                power(phi, n, one).b
+            }
+        }
+    }
+}
+
+impl LucasFinder for BinetZ5 {
+    fn fib_lucas_pair(&mut self, n: u64) -> (Integer, Integer) {
+        // phi^n = L(n)/2 + F(n)/2 root 5, so we get both at once
+        let ans = power(Z5::new(1, 1), n, Z5::one());
+        (ans.b, ans.a)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use rug::ops::Pow;
+    use crate::bigint::{ops::Pow, TEST_HUGE_INDICES};
 
     use super::*;
+    use crate::{Cassini, DPIterator, Zmod};
 
     #[test]
     fn test_fib() {
@@ -145,15 +474,17 @@ mod tests {
             8242546875_u64
         );
 
-        assert_eq!(
-            alg.fib(10_000_000) % (Integer::from(10).pow(10)),
-            6380546875_u64
-        );
+        if TEST_HUGE_INDICES {
+            assert_eq!(
+                alg.fib(10_000_000) % (Integer::from(10).pow(10)),
+                6380546875_u64
+            );
 
-        assert_eq!(
-            alg.fib(100_000_000) % (Integer::from(10).pow(10)),
-            7760546875_u64
-        );
+            assert_eq!(
+                alg.fib(100_000_000) % (Integer::from(10).pow(10)),
+                7760546875_u64
+            );
+        }
 
         // assert_eq!(
         //     alg.fib(1_000_000_000) % (Integer::from(10).pow(10)),
@@ -166,4 +497,104 @@ mod tests {
         //     9560546875_u64
         // );
     }
+
+    #[test]
+    fn test_inverse() {
+        let phi = Z5::new(1, 1);
+        let phi_inv = phi.inverse().unwrap();
+        assert_eq!(phi.clone() * phi_inv.clone(), Z5::one());
+        assert_eq!(phi_inv.inverse().unwrap(), phi);
+        assert_eq!(Z5::one().inverse().unwrap(), Z5::one());
+
+        // 1 + root 5 has norm -4
+        assert_eq!(Z5::new(2, 2).inverse(), None);
+    }
+
+    #[test]
+    fn test_fib_mod_prime() {
+        let mut alg = BinetZ5::default();
+        let mut reference = Cassini::default();
+        let mersenne_61 = Integer::from(Integer::u_pow_u(2, 61)) - 1;
+        let mersenne_127 = Integer::from(Integer::u_pow_u(2, 127)) - 1;
+        // a mix of p = 0, 1, 2, 3 and 4 mod 5
+        let primes = [
+            2.into(),
+            3.into(),
+            5.into(),
+            7.into(),
+            11.into(),
+            29.into(),
+            31.into(),
+            1_000_000_007.into(),
+            1_000_000_009.into(),
+            998_244_353.into(),
+            mersenne_61,
+            mersenne_127,
+        ];
+        for p in primes.iter() {
+            for n in [0, 1, 2, 3, 4, 5, 12, 37, 100, 1000, 1001, 10000, u64::MAX] {
+                assert_eq!(alg.fib_mod_prime(n, p), reference.fib_mod(n, p));
+            }
+        }
+
+        for p in [11u32, 19, 29, 41, 1_000_000_009] {
+            let p = Integer::from(p);
+            assert_eq!(sqrt5_mod(&p).square() % &p, 5);
+        }
+    }
+
+    #[test]
+    fn test_zsqrt5() {
+        const M: u64 = 1_000_000_007;
+        let mut alg = BinetZ5::default();
+        let m = Integer::from(M);
+        // (1 + sqrt 5)^n = 2^n phi^n = 2^(n - 1) (L(n) + F(n) sqrt 5)
+        let phi2 = Zsqrt5::<Zmod<M>>::new(Zmod::new(1), Zmod::new(1));
+        for n in [1, 2, 3, 12, 100, 1000, 1001, 10000] {
+            let (f_n, l_n) = alg.fib_lucas_pair(n);
+            let two_pow = Zmod::<M>::new(2).pow(n - 1);
+            let expected = Zsqrt5::new(
+                two_pow * Zmod::new((l_n % &m).to_u64().unwrap()),
+                two_pow * Zmod::new((f_n % &m).to_u64().unwrap()),
+            );
+            assert_eq!(phi2.pow(n), expected);
+        }
+
+        // norms multiply
+        let x = Zsqrt5::new(Integer::from(3), Integer::from(-7));
+        let y = Zsqrt5::new(Integer::from(12), Integer::from(5));
+        assert_eq!((x.clone() * &y).norm(), x.norm() * y.norm());
+        assert_eq!(x.clone() * x.conjugate(), Zsqrt5::new(x.norm(), 0.into()));
+        assert_eq!(Zsqrt5::<Integer>::sqrt5().square(), Zsqrt5::from_small(5));
+
+        // 5 isn't a square mod 7, so this is GF(49): F(n) lands in GF(7)
+        for n in [0, 1, 2, 12, 1000, u64::MAX] {
+            let f_n = alg.fib_mod(n, &Integer::from(7)).to_u64().unwrap();
+            assert_eq!(
+                Cassini::default().fib_ring::<Zsqrt5<Zmod<7>>>(n),
+                Zsqrt5::new(Zmod::new(f_n), Zmod::new(0))
+            );
+        }
+    }
+
+    #[test]
+    fn test_fib_signed() {
+        let mut alg = BinetZ5::default();
+        let negafib = [0i32, 1, -1, 2, -3, 5, -8, 13, -21, 34, -55];
+        for (n, f_n) in negafib.iter().enumerate() {
+            assert_eq!(alg.fib_signed(-(n as i64)), *f_n);
+        }
+
+        for n in -100..100 {
+            assert_eq!(
+                alg.fib_signed(n),
+                alg.fib_signed(n - 1) + alg.fib_signed(n - 2)
+            );
+        }
+
+        let mut reference = DPIterator::default();
+        for n in [-1000, -1001, -10000, 1000, 10000] {
+            assert_eq!(alg.fib_signed(n), reference.fib_signed(n));
+        }
+    }
 }
@@ -0,0 +1,311 @@
+//! Times the algorithms on this machine to find where `Auto` should switch
+//! strategies, the same way the benchmarks sweep n from small to huge, and
+//! reads and writes the results as a small JSON tuning file.
+
+use std::{
+    fs,
+    hint::black_box,
+    io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    auto::{fib_with, Crossovers, Strategy},
+    registry::algorithms,
+    small::MAX_U128_INDEX,
+};
+
+/// The indices `calibrate` times by default, roughly following the benchmark
+/// groups from small to large.
+pub const DEFAULT_POINTS: [u64; 20] = [
+    2,
+    5,
+    10,
+    25,
+    50,
+    100,
+    150,
+    186,
+    200,
+    800,
+    1600,
+    6400,
+    25600,
+    100_000,
+    400_000,
+    1_600_000,
+    6_400_000,
+    25_600_000,
+    100_000_000,
+    1_000_000_000,
+];
+
+/// How long one algorithm took to find F(n), on average.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timing {
+    /// The name of the algorithm, or of the `Auto` strategy.
+    pub name: String,
+    /// The index.
+    pub n: u64,
+    /// The mean time per call, in nanoseconds.
+    pub nanos: f64,
+}
+
+/// The results of calibrating.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Calibration {
+    /// Every measurement, in the order they were taken.
+    pub timings: Vec<Timing>,
+    /// The crossover points the measurements suggest.
+    pub crossovers: Crossovers,
+}
+
+impl Calibration {
+    /// Writes the results as JSON, which `load_tuning` reads back.
+    pub fn to_json(&self) -> String {
+        let timings: Vec<String> = self
+            .timings
+            .iter()
+            .map(|t| {
+                format!(
+                    "    {{\"name\": \"{}\", \"n\": {}, \"nanos\": {:.1}}}",
+                    t.name, t.n, t.nanos
+                )
+            })
+            .collect();
+        format!(
+            "{{\n  \"small_max\": {},\n  \"huge_min\": {},\n  \"timings\": [\n{}\n  ]\n}}\n",
+            self.crossovers.small_max,
+            self.crossovers.huge_min,
+            timings.join(",\n")
+        )
+    }
+
+    /// Writes the results to a tuning file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+}
+
+/// Times every registered algorithm that can handle each index in points, and
+/// each of `Auto`'s strategies, spending about time_per_point on each. Points
+/// past 100 million take a while no matter how small time_per_point is.
+pub fn calibrate(points: &[u64], time_per_point: Duration) -> Calibration {
+    let mut timings = Vec::new();
+    for &n in points {
+        for alg in algorithms() {
+            if alg.name != "Auto" && n <= alg.max_safe_n {
+                let nanos = time(|| alg.make().fib(n), time_per_point);
+                timings.push(Timing {
+                    name: alg.name.to_owned(),
+                    n,
+                    nanos,
+                });
+            }
+        }
+
+        let strategies = [
+            (Strategy::Small, "Auto (small)"),
+            (Strategy::Medium, "Auto (medium)"),
+            (Strategy::Huge, "Auto (huge)"),
+        ];
+        for (strategy, name) in strategies {
+            if strategy == Strategy::Small && n > MAX_U128_INDEX {
+                continue;
+            }
+            let nanos = time(|| fib_with(strategy, n), time_per_point);
+            timings.push(Timing {
+                name: name.to_owned(),
+                n,
+                nanos,
+            });
+        }
+    }
+
+    let crossovers = pick_crossovers(&timings);
+    Calibration {
+        timings,
+        crossovers,
+    }
+}
+
+/// Calls f repeatedly for about the given time, after one call to warm up, and
+/// returns the mean time per call in nanoseconds.
+fn time<T>(mut f: impl FnMut() -> T, time: Duration) -> f64 {
+    black_box(f());
+    let start = Instant::now();
+    let mut calls = 0u32;
+    while calls == 0 || start.elapsed() < time {
+        black_box(f());
+        calls += 1;
+    }
+    start.elapsed().as_nanos() as f64 / f64::from(calls)
+}
+
+/// Finds the crossovers from timings of the `Auto` strategies. The small
+/// strategy is used for as long as it beats the medium one, and the huge one
+/// from the point where it starts beating the medium one for good, by enough
+/// that it isn't just noise. Anything that wasn't measured keeps its default.
+fn pick_crossovers(timings: &[Timing]) -> Crossovers {
+    let nanos = |name: &str, n: u64| {
+        timings
+            .iter()
+            .find(|t| t.name == name && t.n == n)
+            .map(|t| t.nanos)
+    };
+    let mut points: Vec<u64> = timings.iter().map(|t| t.n).collect();
+    points.sort_unstable();
+    points.dedup();
+
+    let mut crossovers = Crossovers::default();
+
+    let mut small_max = None;
+    for &n in &points {
+        match (nanos("Auto (small)", n), nanos("Auto (medium)", n)) {
+            (Some(small), Some(medium)) if small <= medium => small_max = Some(n),
+            (Some(_), Some(_)) => break,
+            _ => {}
+        }
+    }
+    if let Some(small_max) = small_max {
+        crossovers.small_max = small_max;
+    }
+
+    let mut huge_min = None;
+    for &n in &points {
+        if let (Some(huge), Some(medium)) = (nanos("Auto (huge)", n), nanos("Auto (medium)", n)) {
+            if huge < 0.95 * medium {
+                huge_min = huge_min.or(Some(n));
+            } else {
+                huge_min = None;
+            }
+        }
+    }
+    if let Some(huge_min) = huge_min {
+        crossovers.huge_min = huge_min;
+    }
+
+    crossovers
+}
+
+/// Reads the crossovers from a tuning file written by `Calibration::save`.
+pub fn load_tuning(path: impl AsRef<Path>) -> io::Result<Crossovers> {
+    let json = fs::read_to_string(path)?;
+    parse_tuning(&json).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "tuning file needs small_max and huge_min",
+        )
+    })
+}
+
+/// Pulls the crossovers out of the JSON `Calibration::to_json` writes. This
+/// isn't a general JSON parser: it just looks for the two keys it needs.
+fn parse_tuning(json: &str) -> Option<Crossovers> {
+    Some(Crossovers {
+        small_max: json_u64(json, "small_max")?,
+        huge_min: json_u64(json, "huge_min")?,
+    })
+}
+
+/// Finds the unsigned integer value of the given key.
+fn json_u64(json: &str, key: &str) -> Option<u64> {
+    let key = format!("\"{}\"", key);
+    let rest = json[json.find(&key)? + key.len()..].trim_start();
+    let rest = rest.strip_prefix(':')?.trim_start();
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(name: &str, n: u64, nanos: f64) -> Timing {
+        Timing {
+            name: name.to_owned(),
+            n,
+            nanos,
+        }
+    }
+
+    #[test]
+    fn test_pick_crossovers() {
+        let timings = vec![
+            timing("Auto (small)", 10, 5.0),
+            timing("Auto (medium)", 10, 20.0),
+            timing("Auto (huge)", 10, 30.0),
+            timing("Auto (small)", 100, 15.0),
+            timing("Auto (medium)", 100, 20.0),
+            timing("Auto (huge)", 100, 10.0),
+            timing("Auto (small)", 150, 25.0),
+            timing("Auto (medium)", 150, 20.0),
+            timing("Auto (huge)", 150, 30.0),
+            timing("Auto (medium)", 1000, 200.0),
+            timing("Auto (huge)", 1000, 100.0),
+            timing("Auto (medium)", 5000, 900.0),
+            timing("Auto (huge)", 5000, 800.0),
+        ];
+        assert_eq!(
+            pick_crossovers(&timings),
+            Crossovers {
+                small_max: 100,
+                huge_min: 1000
+            }
+        );
+
+        assert_eq!(pick_crossovers(&[]), Crossovers::default());
+    }
+
+    #[test]
+    fn test_calibrate() {
+        let calibration = calibrate(&[5, 200, 1000], Duration::from_micros(100));
+        // only algorithms that can handle n get timed
+        assert!(calibration
+            .timings
+            .iter()
+            .any(|t| t.name == "NaiveRecursor" && t.n == 5));
+        assert!(!calibration
+            .timings
+            .iter()
+            .any(|t| t.name == "NaiveRecursor" && t.n == 200));
+        assert!(calibration
+            .timings
+            .iter()
+            .any(|t| t.name == "Auto (small)" && t.n == 5));
+        assert!(!calibration
+            .timings
+            .iter()
+            .any(|t| t.name == "Auto (small)" && t.n == 200));
+        assert!(calibration.timings.iter().all(|t| t.nanos > 0.0));
+    }
+
+    #[test]
+    fn test_json() {
+        let calibration = Calibration {
+            timings: vec![timing("GMP", 10, 21.5), timing("Cassini", 10, 383.3)],
+            crossovers: Crossovers {
+                small_max: 150,
+                huge_min: 123_456_789,
+            },
+        };
+        let json = calibration.to_json();
+        assert!(json.contains("{\"name\": \"Cassini\", \"n\": 10, \"nanos\": 383.3}"));
+        assert_eq!(parse_tuning(&json), Some(calibration.crossovers));
+
+        assert_eq!(
+            parse_tuning("{\"huge_min\":7,\"small_max\" : 3}"),
+            Some(Crossovers {
+                small_max: 3,
+                huge_min: 7
+            })
+        );
+        assert_eq!(parse_tuning("{\"small_max\": 3}"), None);
+        assert_eq!(parse_tuning("{\"huge_min\": 7}"), None);
+        assert_eq!(parse_tuning("{\"small_max\": -3, \"huge_min\": 7}"), None);
+        assert_eq!(parse_tuning("{\"small_max\": 3, \"huge_min\": -7}"), None);
+    }
+}
@@ -4,55 +4,78 @@
 //! exponentiation by squaring can then be adapted to get to any number using
 //! these two recursions.
 
-use rug::Complete;
-use rug::Integer;
-
-use crate::fib_finder::FibFinder;
+use crate::{bigint::Integer, fib_finder::FibFinder, lucas_finder::LucasFinder, ring::Ring};
 
 /// Cassini's identity recursion approach.
 #[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
 pub struct Cassini {}
 
+impl Cassini {
+    /// Finds the nth Fibonacci number in any ring, like F(n) mod 2^128 with
+    /// `Wrapping<u128>`.
+    pub fn fib_ring<T: Ring>(&mut self, n: u64) -> T {
+        fib_and_next(n).0
+    }
+}
+
 impl FibFinder for Cassini {
-    fn fib(&mut self, n: u64) -> rug::Integer {
+    fn fib(&mut self, n: u64) -> Integer {
         if n < 2 {
             return n.into();
         }
 
-        let bits = format!("{:b}", n);
+        self.fib_ring(n)
+    }
 
-        let mut i = 1;
+    fn fib_pair(&mut self, n: u64) -> (Integer, Integer) {
+        fib_and_next(n)
+    }
+}
 
-        let mut f_i = Integer::from(1u32);
-        let mut f_iplus1 = Integer::from(1u32);
+impl LucasFinder for Cassini {
+    fn fib_lucas_pair(&mut self, n: u64) -> (Integer, Integer) {
+        // L(n) = F(n - 1) + F(n + 1) = 2 F(n + 1) - F(n)
+        let (f_n, f_nplus1) = fib_and_next::<Integer>(n);
+        let l_n = (f_nplus1 << 1u32) - &f_n;
+        (f_n, l_n)
+    }
+}
 
-        let two = Integer::from(2u32);
+/// Runs the recursion over the bits of n, returning F(n) and F(n + 1) in any
+/// ring.
+fn fib_and_next<T: Ring>(n: u64) -> (T, T) {
+    if n == 0 {
+        return (T::zero(), T::one());
+    }
 
-        for b in bits.chars().skip(1) {
-            let f_i_sqr = f_i.square_ref().complete();
-            let f_i_iplus1 = f_i * &f_iplus1;
-            let f_iplus1_sqr = f_iplus1.square();
-            let f_2iplus1 = (&f_i_sqr + &f_iplus1_sqr).complete();
+    let bits = format!("{:b}", n);
 
-            let double_f_i_iplus1 = f_i_iplus1 * &two;
-            if b == '0' {
-                i = 2 * i;
-                (f_i, f_iplus1) = (double_f_i_iplus1 - f_i_sqr, f_2iplus1);
-            } else {
-                i = 2 * i + 1;
-                (f_i, f_iplus1) = (f_2iplus1, double_f_i_iplus1 + f_iplus1_sqr);
-            }
-        }
+    let mut i = 1;
 
-        assert!(i == n);
+    let mut f_i = T::one();
+    let mut f_iplus1 = T::one();
 
-        f_i
+    for b in bits.chars().skip(1) {
+        let f_i_sqr = f_i.square();
+        let f_iplus1_sqr = f_iplus1.square();
+        let double_f_i_iplus1 = (f_i * &f_iplus1).mul_2exp(1);
+        if b == '0' {
+            i *= 2;
+            (f_i, f_iplus1) = (double_f_i_iplus1 - &f_i_sqr, f_i_sqr + f_iplus1_sqr);
+        } else {
+            i = 2 * i + 1;
+            (f_i, f_iplus1) = (f_i_sqr + &f_iplus1_sqr, double_f_i_iplus1 + f_iplus1_sqr);
+        }
     }
+
+    debug_assert_eq!(i, n);
+
+    (f_i, f_iplus1)
 }
 
 #[cfg(test)]
 mod tests {
-    use rug::ops::Pow;
+    use crate::bigint::{ops::Pow, TEST_HUGE_INDICES};
 
     use super::*;
 
@@ -91,15 +114,17 @@ mod tests {
             8242546875_u64
         );
 
-        assert_eq!(
-            alg.fib(10_000_000) % (Integer::from(10).pow(10)),
-            6380546875_u64
-        );
+        if TEST_HUGE_INDICES {
+            assert_eq!(
+                alg.fib(10_000_000) % (Integer::from(10).pow(10)),
+                6380546875_u64
+            );
 
-        assert_eq!(
-            alg.fib(100_000_000) % (Integer::from(10).pow(10)),
-            7760546875_u64
-        );
+            assert_eq!(
+                alg.fib(100_000_000) % (Integer::from(10).pow(10)),
+                7760546875_u64
+            );
+        }
 
         // assert_eq!(
         //     alg.fib(1_000_000_000) % (Integer::from(10).pow(10)),
@@ -111,4 +136,26 @@ mod tests {
         //     9560546875_u64
         // );
     }
+
+    #[test]
+    fn test_signed() {
+        let mut alg = Cassini::default();
+        let negafib = [0i32, 1, -1, 2, -3, 5, -8, 13, -21, 34, -55];
+        let negalucas = [2i32, -1, 3, -4, 7, -11, 18, -29, 47, -76, 123];
+        for n in 0..negafib.len() {
+            assert_eq!(alg.fib_signed(-(n as i64)), negafib[n]);
+            assert_eq!(alg.lucas_signed(-(n as i64)), negalucas[n]);
+        }
+
+        for n in -100..100 {
+            assert_eq!(
+                alg.fib_signed(n),
+                alg.fib_signed(n - 1) + alg.fib_signed(n - 2)
+            );
+            assert_eq!(
+                alg.lucas_signed(n),
+                alg.lucas_signed(n - 1) + alg.lucas_signed(n - 2)
+            );
+        }
+    }
 }
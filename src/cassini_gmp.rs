@@ -1,57 +1,123 @@
 //! Basically a direct port of the GMP library's Fibonacci function, an
 //! optimized version of the Cassini approach.
 
-use rug::{Complete, Integer};
-
-use crate::fib_finder::FibFinder;
+use crate::{
+    bigint::{Complete, Integer},
+    fib_finder::FibFinder,
+    lucas_finder::LucasFinder,
+};
 
 /// Cassini's identity recursion approach.
 #[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
 pub struct CassiniGMP {}
 
 impl FibFinder for CassiniGMP {
-    fn fib(&mut self, n: u64) -> rug::Integer {
+    fn fib(&mut self, n: u64) -> Integer {
         if n < 2 {
             return n.into();
         }
 
         let bits = format!("{:b}", n);
 
-        let mut i = 1;
-        let mut f_i = Integer::from(1u32);
-        let mut f_im1 = Integer::from(0u32);
-
-        let mut next_offset = -2i8;
-
-        for b in bits.chars().skip(1) {
-            let f_i_sqr = f_i.square_ref().complete();
-            let f_im1_sqr = f_im1.square_ref().complete();
-            // F[2i-1] = F[i]^2 + F[i-1]^2
-            let f_2im1 = (&f_i_sqr + &f_im1_sqr).complete();
-            // F[2i+1] = 4 F[k]^2 - F[i-1]^2 + 2*(-1)^i
-            let f_2ip1 = (f_i_sqr << 2u32) - f_im1_sqr + next_offset;
-            // F[2i] = F[2i+1] - F[2i-1]
-            let f_2i = (&f_2ip1 - &f_2im1).complete();
-            if b == '0' {
-                i = 2 * i;
-                (f_i, f_im1) = (f_2i, f_2im1);
-                next_offset = 2;
-            } else {
-                i = 2 * i + 1;
-                (f_i, f_im1) = (f_2ip1, f_2i);
-                next_offset = -2;
-            }
+        let (f_n, _f_nm1) = double_from(
+            Integer::from(1u32),
+            Integer::from(0u32),
+            true,
+            bits.chars().skip(1).map(|b| b == '1'),
+        );
+
+        f_n
+    }
+
+    fn fib_pair(&mut self, n: u64) -> (Integer, Integer) {
+        if n == 0 {
+            return (0.into(), 1.into());
         }
 
-        assert!(i == n);
+        let bits = format!("{:b}", n);
+
+        let (f_n, f_nm1) = double_from(
+            Integer::from(1u32),
+            Integer::from(0u32),
+            true,
+            bits.chars().skip(1).map(|b| b == '1'),
+        );
+
+        let f_np1 = (&f_n + &f_nm1).complete();
+        (f_n, f_np1)
+    }
+}
+
+impl LucasFinder for CassiniGMP {
+    fn fib_lucas_pair(&mut self, n: u64) -> (Integer, Integer) {
+        if n == 0 {
+            return (0.into(), 2.into());
+        }
+
+        let bits = format!("{:b}", n);
+
+        let (f_n, f_nm1) = double_from(
+            Integer::from(1u32),
+            Integer::from(0u32),
+            true,
+            bits.chars().skip(1).map(|b| b == '1'),
+        );
 
-        f_i
+        // L[n] = F[n+1] + F[n-1] = F[n] + 2 F[n-1]
+        let l_n = (f_nm1 << 1u32) + &f_n;
+        (f_n, l_n)
     }
 }
 
+/// Starting from F[i] and F[i-1], applies one doubling step per bit, most
+/// significant first, and returns F[j] and F[j-1] for the index j those bits
+/// lead to. i_is_odd says whether the starting index i is odd.
+pub(crate) fn double_from(
+    f_i: Integer,
+    f_im1: Integer,
+    i_is_odd: bool,
+    bits: impl Iterator<Item = bool>,
+) -> (Integer, Integer) {
+    double_from_with(f_i, f_im1, i_is_odd, bits, |f_i, f_im1| {
+        (f_i.square(), f_im1.square())
+    })
+}
+
+/// Like `double_from`, but squares F[i] and F[i-1] at each step with
+/// square_both, which returns the two squares in that order. Nothing else
+/// needs F[i] and F[i-1] after that, so square_both gets to keep them.
+pub(crate) fn double_from_with(
+    mut f_i: Integer,
+    mut f_im1: Integer,
+    i_is_odd: bool,
+    bits: impl Iterator<Item = bool>,
+    mut square_both: impl FnMut(Integer, Integer) -> (Integer, Integer),
+) -> (Integer, Integer) {
+    let mut next_offset = if i_is_odd { -2i8 } else { 2i8 };
+
+    for b in bits {
+        let (f_i_sqr, f_im1_sqr) = square_both(f_i, f_im1);
+        // F[2i-1] = F[i]^2 + F[i-1]^2
+        let f_2im1 = (&f_i_sqr + &f_im1_sqr).complete();
+        // F[2i+1] = 4 F[k]^2 - F[i-1]^2 + 2*(-1)^i
+        let f_2ip1 = (f_i_sqr << 2u32) - f_im1_sqr + next_offset;
+        // F[2i] = F[2i+1] - F[2i-1]
+        let f_2i = (&f_2ip1 - &f_2im1).complete();
+        if b {
+            (f_i, f_im1) = (f_2ip1, f_2i);
+            next_offset = -2;
+        } else {
+            (f_i, f_im1) = (f_2i, f_2im1);
+            next_offset = 2;
+        }
+    }
+
+    (f_i, f_im1)
+}
+
 #[cfg(test)]
 mod tests {
-    use rug::ops::Pow;
+    use crate::bigint::{ops::Pow, TEST_HUGE_INDICES};
 
     use super::*;
 
@@ -90,20 +156,22 @@ mod tests {
             8242546875_u64
         );
 
-        assert_eq!(
-            alg.fib(10_000_000) % (Integer::from(10).pow(10)),
-            6380546875_u64
-        );
-
-        assert_eq!(
-            alg.fib(100_000_000) % (Integer::from(10).pow(10)),
-            7760546875_u64
-        );
-
-        assert_eq!(
-            alg.fib(1_000_000_000) % (Integer::from(10).pow(10)),
-            1560546875_u64
-        );
+        if TEST_HUGE_INDICES {
+            assert_eq!(
+                alg.fib(10_000_000) % (Integer::from(10).pow(10)),
+                6380546875_u64
+            );
+
+            assert_eq!(
+                alg.fib(100_000_000) % (Integer::from(10).pow(10)),
+                7760546875_u64
+            );
+
+            assert_eq!(
+                alg.fib(1_000_000_000) % (Integer::from(10).pow(10)),
+                1560546875_u64
+            );
+        }
 
         // assert_eq!(
         //     alg.fib(10_000_000_000) % (Integer::from(10).pow(10)),
@@ -2,14 +2,23 @@
 //! sequence to an iterative one, avoiding stack overflows and only computing
 //! each value once.
 
-use rug::Integer;
-
-use crate::fib_finder::FibFinder;
+use crate::{
+    bigint::Integer,
+    error::FibError,
+    fib_finder::{check_size, FibFinder},
+};
 
 /// Dynamic programming approach: iterates through all of the sequence to reach the given target.
 #[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
 pub struct DPIterator {}
 
+impl DPIterator {
+    /// The biggest index `try_fib` takes. Every index on the way there costs
+    /// an addition as long as F(n), so the time grows with n^2 and much past
+    /// this takes minutes.
+    pub const MAX_INDEX: u64 = 1_000_000;
+}
+
 impl FibFinder for DPIterator {
     fn fib(&mut self, n: u64) -> Integer {
         match n {
@@ -28,11 +37,34 @@ impl FibFinder for DPIterator {
             }
         }
     }
+
+    fn fib_pair(&mut self, n: u64) -> (Integer, Integer) {
+        let mut curr = Integer::from(0);
+        let mut next = Integer::from(1);
+        let mut new;
+        for _ in 0..n {
+            new = next.clone() + curr;
+            curr = next;
+            next = new;
+        }
+        (curr, next)
+    }
+
+    fn try_fib(&mut self, n: u64) -> Result<Integer, FibError> {
+        check_size(n)?;
+        if n > Self::MAX_INDEX {
+            return Err(FibError::UnsupportedRange {
+                n,
+                max: Self::MAX_INDEX,
+            });
+        }
+        Ok(self.fib(n))
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use rug::ops::Pow;
+    use crate::bigint::ops::Pow;
 
     use super::*;
 
@@ -61,4 +93,21 @@ mod tests {
 
         assert_eq!(alg.fib(10000) % (Integer::from(10).pow(10)), 9947366875_u64);
     }
+
+    #[test]
+    fn test_try_fib() {
+        let mut alg = DPIterator::default();
+        assert_eq!(alg.try_fib(1000), Ok(alg.fib(1000)));
+        assert_eq!(
+            alg.try_fib(5_000_000),
+            Err(FibError::UnsupportedRange {
+                n: 5_000_000,
+                max: DPIterator::MAX_INDEX
+            })
+        );
+        assert!(matches!(
+            alg.try_fib(u64::MAX),
+            Err(FibError::ResourceLimit { .. })
+        ));
+    }
 }
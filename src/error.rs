@@ -0,0 +1,99 @@
+//! Errors for the fallible versions of the algorithms, for when it's better to
+//! know up front that an answer is out of reach than to crash trying.
+
+use std::{error::Error, fmt::Display};
+
+/// GMP can't store integers with more bits than this, and long before that
+/// most machines will run out of memory.
+pub const MAX_FIB_BITS: u64 = 1 << 37;
+
+/// The biggest n for which F(n) has at most `MAX_FIB_BITS` bits.
+pub const MAX_FIB_INDEX: u64 = 197_969_829_786;
+
+/// Estimates the number of bits in F(n), which is about n log2(phi). This is
+/// never more than one bit off.
+pub(crate) fn fib_bits_estimate(n: u64) -> u64 {
+    (n as f64 * 0.694_241_913_630_617_3).ceil() as u64
+}
+
+/// The ways finding a Fibonacci number can fail.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum FibError {
+    /// F(n) would need more bits than we're willing to allocate.
+    ResourceLimit {
+        /// The index.
+        n: u64,
+        /// About how many bits F(n) has.
+        bits: u64,
+    },
+    /// Getting F(n) exactly would need more floating-point precision than is
+    /// available.
+    PrecisionOverflow {
+        /// The index.
+        n: u64,
+    },
+    /// The algorithm can't handle indices this big, even though the answer
+    /// itself would fit.
+    UnsupportedRange {
+        /// The index.
+        n: u64,
+        /// The biggest index the algorithm takes.
+        max: u64,
+    },
+}
+
+impl Display for FibError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FibError::ResourceLimit { n, bits } => write!(
+                f,
+                "F({}) has about {} bits, more than the limit of {}",
+                n, bits, MAX_FIB_BITS
+            ),
+            FibError::PrecisionOverflow { n } => {
+                write!(f, "not enough floating-point precision to find F({})", n)
+            }
+            FibError::UnsupportedRange { n, max } => write!(
+                f,
+                "index {} is out of range for this algorithm, which goes up to {}",
+                n, max
+            ),
+        }
+    }
+}
+
+impl Error for FibError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cassini, FibFinder};
+
+    #[test]
+    fn test_fib_bits_estimate() {
+        let mut alg = Cassini::default();
+        for n in [2, 3, 10, 100, 1000, 1001, 10000, 123456] {
+            let bits = u64::from(alg.fib(n).significant_bits());
+            assert!(fib_bits_estimate(n).abs_diff(bits) <= 1);
+        }
+    }
+
+    #[test]
+    fn test_max_fib_index() {
+        assert!(fib_bits_estimate(MAX_FIB_INDEX) <= MAX_FIB_BITS);
+        assert!(fib_bits_estimate(MAX_FIB_INDEX + 1) > MAX_FIB_BITS);
+    }
+
+    #[test]
+    fn test_try_fib() {
+        let mut alg = Cassini::default();
+        assert_eq!(alg.try_fib(100), Ok(alg.fib(100)));
+        assert_eq!(
+            alg.try_fib(u64::MAX),
+            Err(FibError::ResourceLimit {
+                n: u64::MAX,
+                bits: fib_bits_estimate(u64::MAX)
+            })
+        );
+    }
+}
@@ -1,9 +1,286 @@
 //! Trait that abstracts implementations of algorithms to find the nth Fibonacci number.
 
-use rug::Integer;
+use crate::{
+    bigint::{Assign, Complete, Integer},
+    error::{fib_bits_estimate, FibError, MAX_FIB_BITS},
+    pisano::pisano_period,
+};
+
+/// Indices with more bits than this get reduced mod the Pisano period before
+/// `fib_mod_big` starts doubling, when the modulus fits in a u64. Finding the
+/// period means factoring the modulus, which for the worst u64 moduli takes
+/// about as long as this many doubling steps.
+const PISANO_MIN_BITS: u32 = 1 << 14;
 
 /// Represents an algorithm for finding the nth Fibonacci number.
 pub trait FibFinder {
     /// Finds the nth Fibonacci number. We define it such that the 0th Fibonacci number is 0.
     fn fib(&mut self, n: u64) -> Integer;
+
+    /// Finds the nth Fibonacci number, or reports why it can't instead of
+    /// panicking or running out of memory. The default implementation only
+    /// checks that the answer isn't too big to store, so algorithms with other
+    /// limits should override this.
+    fn try_fib(&mut self, n: u64) -> Result<Integer, FibError> {
+        check_size(n)?;
+        Ok(self.fib(n))
+    }
+
+    /// Finds the nth Fibonacci number modulo m, as a value between 0 and m - 1.
+    /// The default implementation does the same doubling as `fib_mod_big`, so
+    /// it never builds F(n) itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if m isn't positive.
+    fn fib_mod(&mut self, n: u64, m: &Integer) -> Integer {
+        check_modulus(m);
+        fib_mod_doubling(&Integer::from(n), m)
+    }
+
+    /// Finds the nth Fibonacci number for any integer n, extending the sequence
+    /// backwards using F(-n) = (-1)^(n + 1) F(n).
+    fn fib_signed(&mut self, n: i64) -> Integer {
+        let f_n = self.fib(n.unsigned_abs());
+        if n < 0 && n % 2 == 0 {
+            -f_n
+        } else {
+            f_n
+        }
+    }
+
+    /// Finds the nth and (n + 1)th Fibonacci numbers together, which is enough
+    /// to carry on the sequence from n. The default implementation calls `fib`
+    /// twice, but most of the fast algorithms get the neighbor for free.
+    fn fib_pair(&mut self, n: u64) -> (Integer, Integer) {
+        (self.fib(n), self.fib(n + 1))
+    }
+
+    /// Finds F(start), F(start + 1), ..., F(end - 1). This finds the first two
+    /// with `fib_pair` and then adds forward, which is much faster than calling
+    /// `fib` for each of them.
+    fn fib_range(&mut self, start: u64, end: u64) -> Vec<Integer> {
+        let mut out = vec![Integer::new(); end.saturating_sub(start) as usize];
+        self.fib_range_into(start, &mut out);
+        out
+    }
+
+    /// Fills out with F(start), F(start + 1), ..., like `fib_range`. Reusing the
+    /// same buffer avoids reallocating the numbers each time.
+    fn fib_range_into(&mut self, start: u64, out: &mut [Integer]) {
+        if out.is_empty() {
+            return;
+        }
+
+        let (f_start, f_next) = self.fib_pair(start);
+        out[0].assign(f_start);
+        if out.len() > 1 {
+            out[1].assign(f_next);
+        }
+        for i in 2..out.len() {
+            let (done, rest) = out.split_at_mut(i);
+            rest[0].assign(&done[i - 1] + &done[i - 2]);
+        }
+    }
+
+    /// Finds the nth Fibonacci number modulo m, for indices that may not fit in
+    /// a u64. The default implementation walks the bits of n with fast
+    /// doubling, reducing mod m at every step, so it works for any n and m.
+    /// For indices with thousands of bits and moduli that fit in a u64, it
+    /// first reduces n mod the Pisano period of m, since F(n) mod m only
+    /// depends on that.
+    ///
+    /// # Panics
+    ///
+    /// Panics if n is negative or m isn't positive.
+    fn fib_mod_big(&mut self, n: &Integer, m: &Integer) -> Integer {
+        assert!(*n >= 0, "index must not be negative");
+        check_modulus(m);
+        match m.to_u64() {
+            Some(small_m) if n.significant_bits() > PISANO_MIN_BITS => {
+                let period = pisano_period(small_m);
+                fib_mod_doubling(&Integer::from(n % &period), m)
+            }
+            _ => fib_mod_doubling(n, m),
+        }
+    }
+
+    /// Finds the last k decimal digits of the nth Fibonacci number, as an
+    /// integer. Leading zeros are dropped, as usual.
+    fn fib_last_digits(&mut self, n: &Integer, k: u32) -> Integer {
+        self.fib_mod_big(n, &Integer::from(Integer::u_pow_u(10, k)))
+    }
+}
+
+/// Finds F(n) mod m with F(2k) = F(k) (2 F(k + 1) - F(k)) and F(2k + 1) =
+/// F(k)^2 + F(k + 1)^2, one bit of n at a time from the top.
+pub(crate) fn fib_mod_doubling(n: &Integer, m: &Integer) -> Integer {
+    let mut f_k = Integer::new();
+    let mut f_kplus1 = Integer::from(1u32) % m;
+
+    for i in (0..n.significant_bits()).rev() {
+        // add m so we never take the remainder of a negative number
+        let diff = ((&f_kplus1 << 1u32).complete() - &f_k + m) % m;
+        let f_2k = (&f_k * &diff).complete() % m;
+        let f_2kplus1 = (f_k.square() + f_kplus1.square()) % m;
+        if n.get_bit(i) {
+            f_kplus1 = (&f_2k + &f_2kplus1).complete() % m;
+            f_k = f_2kplus1;
+        } else {
+            f_k = f_2k;
+            f_kplus1 = f_2kplus1;
+        }
+    }
+
+    f_k
+}
+
+/// Checks that m is a modulus `fib_mod` can reduce by, so a bad one fails here
+/// instead of dividing by zero partway through the doubling.
+pub(crate) fn check_modulus(m: &Integer) {
+    assert!(*m > 0, "modulus must be positive");
+}
+
+/// Checks that F(n) isn't too big to compute at all.
+pub(crate) fn check_size(n: u64) -> Result<(), FibError> {
+    let bits = fib_bits_estimate(n);
+    if bits > MAX_FIB_BITS {
+        Err(FibError::ResourceLimit { n, bits })
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        algorithms, bigint::ops::Pow, Auto, BinetZ5, Cassini, Complexity, DPIterator,
+        MatExponentiator, ParallelCassini, GMP,
+    };
+
+    #[test]
+    fn test_fib_mod() {
+        let mut reference = Cassini::default();
+        let moduli = [1u64, 2, 10, 97, 1000, 1_000_000_007, u64::MAX];
+        let ten_digits = Integer::from(10).pow(10);
+        let p = Integer::from(1_000_000_007);
+        for info in algorithms() {
+            let mut alg = info.make();
+            for m in moduli {
+                let m = Integer::from(m);
+                for n in [0, 1, 2, 3, 12, 37, 100, 1000, 1001, 10000] {
+                    let f_n = reference.fib(n) % &m;
+                    assert_eq!(alg.fib_mod(n, &m), f_n, "{}", info.name);
+                }
+            }
+
+            assert_eq!(alg.fib_mod(1_000_000_000, &ten_digits), 1560546875_u64);
+            assert_eq!(alg.fib_mod(10_000_000_000, &ten_digits), 9560546875_u64);
+            assert_eq!(
+                alg.fib_mod(1_000_000_000_000_000_000, &ten_digits),
+                9560546875_u64
+            );
+            assert_eq!(alg.fib_mod(10_000_000_000_000, &p), 400848210_u64);
+            assert_eq!(alg.fib_mod(u64::MAX, &p), 683972503_u64);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "modulus must be positive")]
+    fn test_fib_mod_zero() {
+        Cassini::default().fib_mod(10, &Integer::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "modulus must be positive")]
+    fn test_fib_mod_big_zero() {
+        Cassini::default().fib_mod_big(&Integer::from(10), &Integer::new());
+    }
+
+    #[test]
+    fn test_fib_mod_big() {
+        // MatExponentiator has its own, so it checks the default
+        let mut reference = MatExponentiator::default();
+        let p = Integer::from(1_000_000_007);
+        let two_64 = Integer::from(Integer::u_pow_u(2, 64));
+        let googol = Integer::from(Integer::u_pow_u(10, 100));
+        let big_moduli = [two_64.clone() + 13u32, googol.clone() - 1u32];
+        let big_indices = [two_64.clone(), two_64.clone() * 3u32 + 1u32, googol.clone()];
+        for info in algorithms() {
+            let mut alg = info.make();
+            for n in [0u64, 1, 2, 3, 12, 37, 100, 1000, 1001, u64::MAX] {
+                let f_n = alg.fib_mod(n, &p);
+                assert_eq!(alg.fib_mod_big(&Integer::from(n), &p), f_n, "{}", info.name);
+            }
+
+            assert_eq!(alg.fib_last_digits(&two_64, 10), 8089840187_u64);
+            assert_eq!(
+                alg.fib_last_digits(&(two_64.clone() + 1u32), 10),
+                1452839197_u64
+            );
+            assert_eq!(alg.fib_last_digits(&googol, 10), 9560546875_u64);
+            assert_eq!(alg.fib_mod_big(&googol, &p), 175077019_u64);
+
+            // indices and moduli both past u64
+            for m in &big_moduli {
+                for n in &big_indices {
+                    let f_n = reference.fib_mod_big(n, m);
+                    assert_eq!(alg.fib_mod_big(n, m), f_n, "{}", info.name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fib_pair() {
+        for info in algorithms() {
+            let mut alg = info.make();
+            for n in [0, 1, 2, 3, 12, 37, 100, 1000, 1001, 10000] {
+                // the exponential ones would take all day
+                if n < info.max_safe_n && info.complexity > Complexity::Exponential {
+                    assert_eq!(
+                        alg.fib_pair(n),
+                        (alg.fib(n), alg.fib(n + 1)),
+                        "{}",
+                        info.name
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fib_range() {
+        let finders: Vec<Box<dyn FibFinder>> = vec![
+            Box::new(Cassini::default()),
+            Box::new(GMP::default()),
+            Box::new(DPIterator::default()),
+            Box::new(MatExponentiator::default()),
+            Box::new(BinetZ5::default()),
+            Box::new(ParallelCassini::default()),
+            Box::new(Auto::default()),
+        ];
+        let mut reference = Cassini::default();
+        for mut alg in finders {
+            let range = alg.fib_range(1000, 1100);
+            assert_eq!(range.len(), 100);
+            for (n, f_n) in (1000..1100).zip(&range) {
+                assert_eq!(*f_n, reference.fib(n));
+            }
+
+            assert_eq!(alg.fib_range(0, 3), vec![0, 1, 1]);
+            assert_eq!(alg.fib_range(5, 6), vec![5]);
+            assert!(alg.fib_range(5, 5).is_empty());
+            assert!(alg.fib_range(5, 2).is_empty());
+
+            // the buffer gets overwritten, whatever was in it
+            let mut buf = vec![Integer::from(-1); 50];
+            alg.fib_range_into(1000, &mut buf);
+            assert_eq!(buf, range[..50]);
+            alg.fib_range_into(0, &mut buf[..2]);
+            assert_eq!(buf[..2], [0, 1]);
+            assert_eq!(buf[2], range[2]);
+        }
+    }
 }
@@ -0,0 +1,152 @@
+//! An iterator over the Fibonacci sequence. Each step is a single addition,
+//! and skipping ahead uses the addition formula
+//! F(k + m) = F(k) F(m - 1) + F(k + 1) F(m), so `nth` and `step_by` don't walk
+//! every number in between.
+
+use crate::{
+    bigint::{Complete, Integer},
+    fib_finder::FibFinder,
+    gmp::GMP,
+};
+
+/// Skips shorter than this are done one step at a time, which is cheaper than
+/// the multiplications a jump takes.
+const MIN_JUMP: u64 = 32;
+
+/// Yields F(k), F(k + 1), F(k + 2), ... starting from any k.
+#[derive(Clone, Debug)]
+pub struct FibIter {
+    /// The index of the next number to yield.
+    index: u64,
+    /// F(index).
+    curr: Integer,
+    /// F(index + 1).
+    next: Integer,
+    /// The last jump taken, as m, F(m) and F(m + 1), since `step_by` keeps
+    /// making the same one.
+    jump: Option<(u64, Integer, Integer)>,
+}
+
+impl FibIter {
+    /// Starts the sequence at F(0).
+    pub fn new() -> Self {
+        FibIter::starting_at(0)
+    }
+
+    /// Starts the sequence at F(k).
+    pub fn starting_at(k: u64) -> Self {
+        let (curr, next) = GMP::default().fib_pair(k);
+        FibIter {
+            index: k,
+            curr,
+            next,
+            jump: None,
+        }
+    }
+
+    /// The index of the number the iterator yields next.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Moves forward m places without yielding anything.
+    fn skip_ahead(&mut self, m: u64) {
+        if m < MIN_JUMP {
+            for _ in 0..m {
+                self.step();
+            }
+            return;
+        }
+
+        let (f_m, f_mp1) = match self.jump.take() {
+            Some((last_m, f_m, f_mp1)) if last_m == m => (f_m, f_mp1),
+            _ => GMP::default().fib_pair(m),
+        };
+
+        // F(k + m) = F(k) F(m - 1) + F(k + 1) F(m)
+        // F(k + m + 1) = F(k) F(m) + F(k + 1) F(m + 1)
+        let f_mm1 = (&f_mp1 - &f_m).complete();
+        let curr = (&self.curr * &f_mm1).complete() + (&self.next * &f_m).complete();
+        let next = (&self.curr * &f_m).complete() + (&self.next * &f_mp1).complete();
+        self.curr = curr;
+        self.next = next;
+        self.index += m;
+        self.jump = Some((m, f_m, f_mp1));
+    }
+
+    /// Moves forward one place, handing back the number moved past. The pair
+    /// shifts down without copying, so the only new number is the sum.
+    fn step(&mut self) -> Integer {
+        let new = (&self.curr + &self.next).complete();
+        let next = std::mem::replace(&mut self.next, new);
+        self.index += 1;
+        std::mem::replace(&mut self.curr, next)
+    }
+}
+
+impl Default for FibIter {
+    fn default() -> Self {
+        FibIter::new()
+    }
+}
+
+impl Iterator for FibIter {
+    type Item = Integer;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.step())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.skip_ahead(n as u64);
+        self.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cassini;
+
+    #[test]
+    fn test_sequence() {
+        let first: Vec<Integer> = FibIter::new().take(20).collect();
+        let mut reference = Cassini::default();
+        for (n, f_n) in first.iter().enumerate() {
+            assert_eq!(*f_n, reference.fib(n as u64));
+        }
+
+        let mut iter = FibIter::starting_at(1000);
+        for n in 1000..1100 {
+            assert_eq!(iter.index(), n);
+            assert_eq!(iter.next().unwrap(), reference.fib(n));
+        }
+    }
+
+    #[test]
+    fn test_nth() {
+        let mut reference = Cassini::default();
+        let mut iter = FibIter::starting_at(7);
+        assert_eq!(iter.nth(5).unwrap(), reference.fib(12));
+        assert_eq!(iter.nth(1000).unwrap(), reference.fib(1013));
+        assert_eq!(iter.next().unwrap(), reference.fib(1014));
+        assert_eq!(iter.next().unwrap(), reference.fib(1015));
+        assert_eq!(iter.index(), 1016);
+    }
+
+    #[test]
+    fn test_step_by() {
+        let mut reference = Cassini::default();
+        for (start, step) in [(0, 1), (0, 7), (5, 31), (100, 32), (3, 1000)] {
+            let strided: Vec<Integer> =
+                FibIter::starting_at(start).step_by(step).take(50).collect();
+            for (i, f_n) in strided.iter().enumerate() {
+                assert_eq!(*f_n, reference.fib(start + (i * step) as u64));
+            }
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Finite fields with p^k elements, GF(p^k), built as the polynomials over
+//! GF(p) modulo an irreducible polynomial of degree k. Which polynomial to use
+//! is part of the field's type, through `Extension`, so that the field can be a
+//! `Ring` like any other.
+
+use std::{
+    fmt::Debug,
+    marker::PhantomData,
+    ops::{Add, Mul, Sub},
+};
+
+use crate::ring::{by_value, Ring};
+
+/// Picks out a field extension: the base field, usually `Zmod<P>` for a prime
+/// P, and the monic irreducible polynomial x^k + c(k-1) x^(k-1) + ... + c(0)
+/// the elements are reduced by.
+pub trait Extension {
+    /// The field the coefficients come from.
+    type Base: Ring + Eq + Debug;
+
+    /// The coefficients of the modulus below the leading 1, c(0) first. The
+    /// degree of the extension is the length.
+    const MODULUS: &'static [u32];
+}
+
+/// An element of the field `E` describes, as a polynomial in x with degree
+/// less than that of the modulus.
+pub struct Gf<E: Extension> {
+    /// The coefficients, constant term first. There are always exactly as many
+    /// as the degree of the extension.
+    coeffs: Vec<E::Base>,
+    _extension: PhantomData<E>,
+}
+
+impl<E: Extension> Gf<E> {
+    /// The element with the given coefficients, constant term first, reduced
+    /// by the modulus if there are too many.
+    pub fn new(mut coeffs: Vec<E::Base>) -> Self {
+        let k = E::MODULUS.len();
+        assert!(k > 0, "the modulus must have degree at least 1");
+        if coeffs.len() < k {
+            coeffs.resize(k, E::Base::zero());
+        }
+        Gf::reduced(coeffs)
+    }
+
+    /// The element with small coefficients, constant term first.
+    pub fn from_coeffs(coeffs: &[u32]) -> Self {
+        Gf::new(coeffs.iter().map(|&c| E::Base::from_small(c)).collect())
+    }
+
+    /// x itself, which generates the field over its base.
+    pub fn x() -> Self {
+        Gf::from_coeffs(&[0, 1])
+    }
+
+    /// The coefficients, constant term first.
+    pub fn coeffs(&self) -> &[E::Base] {
+        &self.coeffs
+    }
+
+    /// Reduces a polynomial with at least k coefficients mod the modulus,
+    /// knocking off the top term with x^k = -(c(k-1) x^(k-1) + ... + c(0))
+    /// until k are left.
+    fn reduced(mut coeffs: Vec<E::Base>) -> Self {
+        let k = E::MODULUS.len();
+        while coeffs.len() > k {
+            let top = coeffs.pop().unwrap();
+            let shift = coeffs.len() - k;
+            for (j, &c) in E::MODULUS.iter().enumerate() {
+                if c != 0 {
+                    let x = std::mem::replace(&mut coeffs[shift + j], E::Base::zero());
+                    coeffs[shift + j] = x - top.clone() * &E::Base::from_small(c);
+                }
+            }
+        }
+
+        Gf {
+            coeffs,
+            _extension: PhantomData,
+        }
+    }
+}
+
+impl<E: Extension> Clone for Gf<E> {
+    fn clone(&self) -> Self {
+        Gf {
+            coeffs: self.coeffs.clone(),
+            _extension: PhantomData,
+        }
+    }
+}
+
+impl<E: Extension> PartialEq for Gf<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.coeffs == other.coeffs
+    }
+}
+
+impl<E: Extension> Eq for Gf<E> {}
+
+impl<E: Extension> Debug for Gf<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Gf").field(&self.coeffs).finish()
+    }
+}
+
+impl<E: Extension> Add<&Gf<E>> for Gf<E> {
+    type Output = Self;
+
+    fn add(mut self, rhs: &Self) -> Self {
+        for (x, y) in self.coeffs.iter_mut().zip(&rhs.coeffs) {
+            *x = std::mem::replace(x, E::Base::zero()) + y;
+        }
+        self
+    }
+}
+
+impl<E: Extension> Sub<&Gf<E>> for Gf<E> {
+    type Output = Self;
+
+    fn sub(mut self, rhs: &Self) -> Self {
+        for (x, y) in self.coeffs.iter_mut().zip(&rhs.coeffs) {
+            *x = std::mem::replace(x, E::Base::zero()) - y;
+        }
+        self
+    }
+}
+
+impl<E: Extension> Mul<&Gf<E>> for Gf<E> {
+    type Output = Self;
+
+    fn mul(self, rhs: &Self) -> Self {
+        // schoolbook multiplication, then reduce the 2k - 1 coefficients
+        let k = E::MODULUS.len();
+        let mut prod = vec![E::Base::zero(); 2 * k - 1];
+        for (i, x) in self.coeffs.iter().enumerate() {
+            for (j, y) in rhs.coeffs.iter().enumerate() {
+                let p = std::mem::replace(&mut prod[i + j], E::Base::zero());
+                prod[i + j] = p + x.clone() * y;
+            }
+        }
+        Gf::reduced(prod)
+    }
+}
+
+by_value!(impl<E: Extension> Gf<E>; Add add, Sub sub, Mul mul);
+
+impl<E: Extension> Ring for Gf<E> {
+    fn from_small(x: u32) -> Self {
+        Gf::from_coeffs(&[x])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bigint::Integer, Cassini, FibFinder, MatExponentiator, Zmod};
+
+    /// GF(2^8) with the modulus AES uses, x^8 + x^4 + x^3 + x + 1.
+    struct Aes;
+
+    impl Extension for Aes {
+        type Base = Zmod<2>;
+        const MODULUS: &'static [u32] = &[1, 1, 0, 1, 1, 0, 0, 0];
+    }
+
+    /// GF(7^2), as GF(7) with a square root of 3 added: x^2 - 3.
+    struct Gf49;
+
+    impl Extension for Gf49 {
+        type Base = Zmod<7>;
+        const MODULUS: &'static [u32] = &[4, 0];
+    }
+
+    #[test]
+    fn test_aes() {
+        // the worked example from FIPS 197: {57} {83} = {c1}
+        let a = Gf::<Aes>::from_coeffs(&[1, 1, 1, 0, 1, 0, 1, 0]);
+        let b = Gf::<Aes>::from_coeffs(&[1, 1, 0, 0, 0, 0, 0, 1]);
+        let c = Gf::<Aes>::from_coeffs(&[1, 0, 0, 0, 0, 0, 1, 1]);
+        assert_eq!(a.clone() * &b, c);
+        assert_eq!(a.clone() + &a, Gf::zero());
+
+        // the nonzero elements form a group of order 255
+        assert_eq!(a.pow(255), Gf::one());
+        assert_eq!(Gf::<Aes>::x().pow(8), Gf::from_coeffs(&[1, 1, 0, 1, 1]));
+    }
+
+    #[test]
+    fn test_gf49() {
+        let x = Gf::<Gf49>::x();
+        assert_eq!(x.square(), Gf::from_small(3));
+        // 3 has order 6 mod 7, so x has order 12
+        assert_eq!(x.pow(6), Gf::from_small(6));
+        assert_eq!(x.pow(12), Gf::one());
+        // and the nonzero elements form a group of order 48
+        assert_eq!((x + &Gf::one()).pow(48), Gf::one());
+        assert_eq!(Gf::<Gf49>::new(vec![Zmod::new(8)]), Gf::one());
+    }
+
+    #[test]
+    fn test_fib_ring() {
+        let mut cassini = Cassini::default();
+        let mut mat = MatExponentiator::default();
+        for n in [0, 1, 2, 3, 12, 100, 1000, 1001, u64::MAX] {
+            // F(n) lands in the prime subfield
+            let f_n =
+                Gf::<Gf49>::from_small(cassini.fib_mod(n, &Integer::from(7)).to_u32().unwrap());
+            assert_eq!(cassini.fib_ring::<Gf<Gf49>>(n), f_n);
+            assert_eq!(mat.fib_ring::<Gf<Gf49>>(n), f_n);
+
+            let parity = cassini.fib_mod(n, &Integer::from(2)).to_u32().unwrap();
+            assert_eq!(cassini.fib_ring::<Gf<Aes>>(n), Gf::from_small(parity));
+        }
+    }
+}
@@ -1,9 +1,22 @@
 //! This module punts on computing Fibonacci numbers ourselves and simply wraps
 //! GMP's algorithm. It's similar to the Cassini approach, but with a couple
 //! more optimizations. https://gmplib.org/manual/Fibonacci-Numbers-Algorithm
+//!
+//! GMP only takes indices that fit in 32 bits, so for bigger ones we ask it for
+//! the Fibonacci numbers at the top 32 bits of the index and then do the rest
+//! of the doublings ourselves.
+//!
+//! With the `pure-rust` feature there's no GMP to punt to, and the stand-in
+//! for its Fibonacci function is plain doubling.
 
-use crate::fib_finder::FibFinder;
-use rug::Integer;
+use std::convert::TryFrom;
+
+use crate::{
+    bigint::{Complete, Integer},
+    cassini_gmp::double_from,
+    fib_finder::FibFinder,
+    lucas_finder::LucasFinder,
+};
 
 /// Wraps GMP.
 #[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
@@ -11,15 +24,69 @@ pub struct GMP {}
 
 impl FibFinder for GMP {
     fn fib(&mut self, n: u64) -> Integer {
-        Integer::fibonacci(n as u32).into()
+        fib_chunked(n, u32::MAX.into())
+    }
+
+    fn fib_pair(&mut self, n: u64) -> (Integer, Integer) {
+        let (f_n, f_nm1) = fib2_chunked(n, u32::MAX.into());
+        let f_np1 = (&f_n + &f_nm1).complete();
+        (f_n, f_np1)
+    }
+}
+
+impl LucasFinder for GMP {
+    fn fib_lucas_pair(&mut self, n: u64) -> (Integer, Integer) {
+        // L(n) = F(n + 1) + F(n - 1) = F(n) + 2 F(n - 1)
+        let (f_n, f_nm1) = fib2_chunked(n, u32::MAX.into());
+        let l_n = (f_nm1 << 1u32) + &f_n;
+        (f_n, l_n)
     }
 }
 
+/// Computes F(n), handing it straight to GMP if n is at most max_direct and
+/// otherwise handing GMP the largest prefix of the bits of n that is.
+/// max_direct must fit in a u32.
+fn fib_chunked(n: u64, max_direct: u64) -> Integer {
+    if n <= max_direct {
+        return Integer::fibonacci(n as u32).into();
+    }
+
+    fib2_chunked(n, max_direct).0
+}
+
+/// Like `fib_chunked`, but returns F(n) and F(n - 1).
+fn fib2_chunked(n: u64, max_direct: u64) -> (Integer, Integer) {
+    if n <= max_direct {
+        return Integer::fibonacci_2(n as u32).into();
+    }
+
+    let (k, shift) = split_index(n, max_direct);
+    let (f_k, f_km1) = <(Integer, Integer)>::from(Integer::fibonacci_2(k));
+    double_from(
+        f_k,
+        f_km1,
+        k % 2 == 1,
+        (0..shift).rev().map(|i| (n >> i) & 1 == 1),
+    )
+}
+
+/// Splits n into its largest prefix k that is at most max_direct, and the
+/// number of bits after it, so that k = n >> shift.
+fn split_index(n: u64, max_direct: u64) -> (u32, u32) {
+    let mut shift = 0;
+    while (n >> shift) > max_direct {
+        shift += 1;
+    }
+    let k = u32::try_from(n >> shift).expect("max_direct must fit in a u32");
+    (k, shift)
+}
+
 #[cfg(test)]
 mod tests {
-    use rug::ops::Pow;
+    use crate::bigint::{ops::Pow, TEST_HUGE_INDICES};
 
     use super::*;
+    use crate::Cassini;
 
     #[test]
     fn test_fib() {
@@ -56,25 +123,86 @@ mod tests {
             8242546875_u64
         );
 
-        assert_eq!(
-            alg.fib(10_000_000) % (Integer::from(10).pow(10)),
-            6380546875_u64
-        );
+        if TEST_HUGE_INDICES {
+            assert_eq!(
+                alg.fib(10_000_000) % (Integer::from(10).pow(10)),
+                6380546875_u64
+            );
 
-        assert_eq!(
-            alg.fib(100_000_000) % (Integer::from(10).pow(10)),
-            7760546875_u64
-        );
+            assert_eq!(
+                alg.fib(100_000_000) % (Integer::from(10).pow(10)),
+                7760546875_u64
+            );
 
-        assert_eq!(
-            alg.fib(1_000_000_000) % (Integer::from(10).pow(10)),
-            1560546875_u64
-        );
+            assert_eq!(
+                alg.fib(1_000_000_000) % (Integer::from(10).pow(10)),
+                1560546875_u64
+            );
+        }
 
-        // this is out of the u32 limit
+        // this is out of the u32 limit, which works now but takes a while
         // assert_eq!(
         //     alg.fib(10_000_000_000) % (Integer::from(10).pow(10)),
         //     9560546875_u64
         // );
     }
+
+    #[test]
+    fn test_fib_chunked() {
+        let mut reference = Cassini::default();
+        for max_direct in [1, 2, 3, 10, 64] {
+            for n in (0..300).chain([1000, 1023, 1024, 1025, 12345]) {
+                assert_eq!(fib_chunked(n, max_direct), reference.fib(n));
+            }
+        }
+
+        for max_direct in [1, 2, 3, 10, 64] {
+            for n in [0, 1, 2, 3, 100, 1000, 1023, 1024, 1025] {
+                let (f_n, f_nm1) = fib2_chunked(n, max_direct);
+                assert_eq!(f_n, Integer::from(Integer::fibonacci(n as u32)));
+                let (_, expected_f_nm1) =
+                    <(Integer, Integer)>::from(Integer::fibonacci_2(n as u32));
+                assert_eq!(f_nm1, expected_f_nm1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fib_u32_boundary() {
+        // the same step past the limit GMP::fib takes at u32::MAX = 2^32 - 1,
+        // at 2^k - 1 for indices small enough to check quickly
+        let mut reference = Cassini::default();
+        for k in 1..=16 {
+            let max_direct = (1 << k) - 1;
+            for n in max_direct - 1..=max_direct + 3 {
+                let (f_n, f_np1) = reference.fib_pair(n);
+                assert_eq!(fib_chunked(n, max_direct), f_n);
+                assert_eq!(fib2_chunked(n, max_direct), (f_n.clone(), f_np1 - f_n));
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_index() {
+        // what GMP gets asked for on either side of its real limit, which used
+        // to wrap around to a small index
+        let max = u64::from(u32::MAX);
+        assert_eq!(split_index(max, max), (u32::MAX, 0));
+        assert_eq!(split_index(max + 1, max), (1 << 31, 1));
+        assert_eq!(split_index(max + 2, max), (1 << 31, 1));
+        assert_eq!(split_index(max + 3, max), ((1 << 31) + 1, 1));
+        assert_eq!(split_index(3 << 32, max), (3 << 30, 2));
+        assert_eq!(split_index(u64::MAX, max), (u32::MAX, 32));
+    }
+
+    #[test]
+    #[cfg(feature = "rug")]
+    #[ignore = "builds two numbers of about 370 MB each"]
+    fn test_fib_u32_boundary_full() {
+        // these used to silently wrap around to small indices
+        let mut alg = GMP::default();
+        let ten_digits = Integer::from(10).pow(10);
+        assert_eq!(alg.fib(u32::MAX as u64 + 1) % &ten_digits, 8208076347_u64);
+        assert_eq!(alg.fib(u32::MAX as u64 + 2) % &ten_digits, 10299677_u64);
+    }
 }
@@ -1,23 +1,62 @@
+mod auto;
+mod batch;
+mod bigint;
+#[cfg(feature = "rug")]
 mod binet;
 mod binet_z5;
+mod calibration;
 mod cassini;
 mod cassini_gmp;
 mod dp_iterator;
+mod error;
 mod fib_finder;
+mod fib_iter;
+mod galois;
 mod gmp;
+mod linear_recurrence;
+mod lucas_finder;
+mod lucas_sequence;
 mod mat_exponentiator;
 mod memoized;
 mod naive;
+mod parallel_cassini;
+mod pisano;
+#[cfg(not(feature = "rug"))]
+mod pure_rust;
+mod registry;
 mod repeated_squaring;
+mod ring;
+#[cfg(feature = "rug")]
+mod size;
+mod small;
+mod zmod;
 
+pub use auto::{Auto, Crossovers};
+pub use batch::fib_batch;
+pub use bigint::Integer;
+#[cfg(feature = "rug")]
 pub use binet::Binet;
-pub use binet_z5::BinetZ5;
+pub use binet_z5::{BinetZ5, Zsqrt5};
+pub use calibration::{calibrate, load_tuning, Calibration, Timing, DEFAULT_POINTS};
 pub use cassini::Cassini;
 pub use cassini_gmp::CassiniGMP;
 pub use dp_iterator::DPIterator;
+pub use error::{FibError, MAX_FIB_BITS, MAX_FIB_INDEX};
 pub use fib_finder::FibFinder;
+pub use fib_iter::FibIter;
+pub use galois::{Extension, Gf};
 pub use gmp::GMP;
-pub use mat_exponentiator::MatExponentiator;
+pub use linear_recurrence::LinearRecurrence;
+pub use lucas_finder::LucasFinder;
+pub use lucas_sequence::LucasSequence;
+pub use mat_exponentiator::{Mat2x2, MatExponentiator, MatNxN};
 pub use memoized::MemoizedRecursor;
 pub use naive::NaiveRecursor;
-pub use rug::Integer;
+pub use parallel_cassini::ParallelCassini;
+pub use pisano::pisano_period;
+pub use registry::{algorithms, find_algorithm, AlgorithmInfo, Complexity};
+pub use ring::Ring;
+#[cfg(feature = "rug")]
+pub use size::{fib_bit_length, fib_num_digits};
+pub use small::{fib_u128, fib_u64, FIB_U128, FIB_U64, MAX_U128_INDEX, MAX_U64_INDEX};
+pub use zmod::Zmod;
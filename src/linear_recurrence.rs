@@ -0,0 +1,227 @@
+//! Linear recurrences with constant coefficients and any number of terms, like
+//! x(n) = c(1) x(n - 1) + ... + c(k) x(n - k). The tribonacci and tetranacci
+//! numbers are the best known after Fibonacci. There are two ways to jump
+//! ahead: raising the k x k companion matrix to the nth power, which takes
+//! O(k^3 log n) multiplications, or Kitamasa's method, which reduces x^n modulo
+//! the characteristic polynomial in O(k^2 log n) and wins for larger k.
+
+use crate::{bigint::Integer, mat_exponentiator::MatNxN, repeated_squaring::power};
+
+/// A linear recurrence with constant coefficients and its first terms.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct LinearRecurrence {
+    /// c(1), ..., c(k): the coefficient of x(n - 1) comes first.
+    coeffs: Vec<Integer>,
+    /// x(0), ..., x(k - 1).
+    initial: Vec<Integer>,
+}
+
+impl LinearRecurrence {
+    /// The recurrence x(n) = c(1) x(n - 1) + ... + c(k) x(n - k), given
+    /// c(1), ..., c(k) and x(0), ..., x(k - 1).
+    pub fn new(coeffs: Vec<Integer>, initial: Vec<Integer>) -> Self {
+        assert!(!coeffs.is_empty(), "recurrence must have at least one term");
+        assert_eq!(
+            coeffs.len(),
+            initial.len(),
+            "need one initial value for each coefficient"
+        );
+        LinearRecurrence { coeffs, initial }
+    }
+
+    /// The k-bonacci numbers, where each term is the sum of the last k and the
+    /// sequence starts with k - 1 zeros and then a 1. k = 2 is Fibonacci.
+    pub fn k_bonacci(k: usize) -> Self {
+        let mut initial = vec![Integer::new(); k];
+        if let Some(last) = initial.last_mut() {
+            *last = 1.into();
+        }
+        LinearRecurrence::new(vec![Integer::from(1); k], initial)
+    }
+
+    /// The tribonacci numbers, 0, 0, 1, 1, 2, 4, 7, ...
+    pub fn tribonacci() -> Self {
+        LinearRecurrence::k_bonacci(3)
+    }
+
+    /// The tetranacci numbers, 0, 0, 0, 1, 1, 2, 4, 8, ...
+    pub fn tetranacci() -> Self {
+        LinearRecurrence::k_bonacci(4)
+    }
+
+    /// The number of terms each term depends on.
+    pub fn order(&self) -> usize {
+        self.coeffs.len()
+    }
+
+    /// Finds x(n), using whichever method should be faster for this order.
+    pub fn term(&self, n: u64) -> Integer {
+        if self.order() <= 3 {
+            self.term_matrix(n)
+        } else {
+            self.term_kitamasa(n)
+        }
+    }
+
+    /// Finds x(n) by raising the companion matrix to the nth power.
+    pub fn term_matrix(&self, n: u64) -> Integer {
+        let k = self.order();
+        let mat = power(MatNxN::companion(&self.coeffs), n, MatNxN::identity(k));
+        // the state vector is (x(k - 1), ..., x(0)), and x(n) is the last
+        // element of the nth power times it
+        (0..k)
+            .map(|j| Integer::from(mat.get(k - 1, j) * &self.initial[k - 1 - j]))
+            .sum()
+    }
+
+    /// Finds x(n) with Kitamasa's method: if x^n = r(0) + r(1) x + ... +
+    /// r(k - 1) x^(k - 1) modulo the characteristic polynomial, then
+    /// x(n) = r(0) x(0) + ... + r(k - 1) x(k - 1).
+    pub fn term_kitamasa(&self, n: u64) -> Integer {
+        let k = self.order();
+        let mut rem = vec![Integer::new(); k];
+        rem[0] = 1.into();
+
+        if n > 0 {
+            let bits = format!("{:b}", n);
+            for b in bits.chars() {
+                rem = self.mul_mod_char_poly(&rem, &rem);
+                if b == '1' {
+                    rem = self.times_x_mod_char_poly(rem);
+                }
+            }
+        }
+
+        rem.iter()
+            .zip(&self.initial)
+            .map(|(r, x)| Integer::from(r * x))
+            .sum()
+    }
+
+    /// Multiplies two polynomials of degree less than k and reduces the product
+    /// modulo the characteristic polynomial x^k - c(1) x^(k - 1) - ... - c(k).
+    fn mul_mod_char_poly(&self, a: &[Integer], b: &[Integer]) -> Vec<Integer> {
+        let k = self.order();
+        let mut prod = vec![Integer::new(); 2 * k - 1];
+        for (i, a_i) in a.iter().enumerate() {
+            if *a_i == 0 {
+                continue;
+            }
+            for (j, b_j) in b.iter().enumerate() {
+                prod[i + j] += a_i * b_j;
+            }
+        }
+
+        // x^i = c(1) x^(i - 1) + ... + c(k) x^(i - k), from the top down
+        for i in (k..2 * k - 1).rev() {
+            let top = std::mem::take(&mut prod[i]);
+            for (j, c) in self.coeffs.iter().enumerate() {
+                prod[i - 1 - j] += &top * c;
+            }
+        }
+        prod.truncate(k);
+        prod
+    }
+
+    /// Multiplies a polynomial of degree less than k by x and reduces it modulo
+    /// the characteristic polynomial.
+    fn times_x_mod_char_poly(&self, mut a: Vec<Integer>) -> Vec<Integer> {
+        let k = self.order();
+        let top = a.pop().unwrap();
+        a.insert(0, Integer::new());
+        for (j, c) in self.coeffs.iter().enumerate() {
+            a[k - 1 - j] += &top * c;
+        }
+        a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cassini, FibFinder};
+
+    /// Steps through the recurrence one term at a time.
+    fn brute_force(rec: &LinearRecurrence, n: usize) -> Vec<Integer> {
+        let k = rec.order();
+        let mut terms = rec.initial.clone();
+        while terms.len() < n {
+            let next = (0..k)
+                .map(|j| Integer::from(&rec.coeffs[j] * &terms[terms.len() - 1 - j]))
+                .sum();
+            terms.push(next);
+        }
+        terms.truncate(n);
+        terms
+    }
+
+    #[test]
+    fn test_tribonacci() {
+        let rec = LinearRecurrence::tribonacci();
+        let first = [0u32, 0, 1, 1, 2, 4, 7, 13, 24, 44, 81, 149, 274, 504];
+        for (n, t_n) in first.iter().enumerate() {
+            assert_eq!(rec.term(n as u64), *t_n);
+            assert_eq!(rec.term_kitamasa(n as u64), *t_n);
+        }
+
+        assert_eq!(
+            rec.term(100),
+            "53324762928098149064722658".parse::<Integer>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_tetranacci() {
+        let rec = LinearRecurrence::tetranacci();
+        let first = [0u32, 0, 0, 1, 1, 2, 4, 8, 15, 29, 56, 108, 208, 401];
+        for (n, t_n) in first.iter().enumerate() {
+            assert_eq!(rec.term(n as u64), *t_n);
+            assert_eq!(rec.term_matrix(n as u64), *t_n);
+        }
+
+        let terms = brute_force(&rec, 1001);
+        assert_eq!(rec.term(1000), terms[1000]);
+        assert_eq!(rec.term_matrix(1000), terms[1000]);
+    }
+
+    #[test]
+    fn test_fibonacci() {
+        let rec = LinearRecurrence::k_bonacci(2);
+        let mut alg = Cassini::default();
+        for n in [0, 1, 2, 3, 12, 37, 100, 1000, 1001, 10000] {
+            assert_eq!(rec.term_matrix(n), alg.fib(n));
+            assert_eq!(rec.term_kitamasa(n), alg.fib(n));
+        }
+    }
+
+    #[test]
+    fn test_general() {
+        let recurrences = [
+            LinearRecurrence::new(vec![3.into()], vec![5.into()]),
+            LinearRecurrence::new(vec![2.into(), (-1).into()], vec![4.into(), 7.into()]),
+            LinearRecurrence::new(
+                vec![1.into(), 0.into(), (-2).into(), 5.into()],
+                vec![1.into(), (-1).into(), 2.into(), 0.into()],
+            ),
+            LinearRecurrence::k_bonacci(1),
+            LinearRecurrence::k_bonacci(7),
+        ];
+        for rec in recurrences {
+            let terms = brute_force(&rec, 200);
+            for (n, x_n) in terms.iter().enumerate() {
+                assert_eq!(rec.term_matrix(n as u64), *x_n);
+                assert_eq!(rec.term_kitamasa(n as u64), *x_n);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mat_nxn() {
+        let fib_mat = MatNxN::from_rows(vec![vec![1.into(), 1.into()], vec![1.into(), 0.into()]]);
+        assert_eq!(fib_mat, MatNxN::companion(&[1.into(), 1.into()]));
+
+        let ans = power(fib_mat, 100, MatNxN::identity(2));
+        assert_eq!(*ans.get(0, 1), Cassini::default().fib(100));
+        assert_eq!(ans.get(0, 1), ans.get(1, 0));
+    }
+}
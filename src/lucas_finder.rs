@@ -0,0 +1,64 @@
+//! Trait for algorithms that find the nth Lucas number alongside the nth
+//! Fibonacci number. Most of the fast algorithms have it in hand anyway.
+
+use crate::{bigint::Integer, fib_finder::FibFinder};
+
+/// Represents an algorithm for finding the nth Lucas number. The Lucas numbers
+/// follow the same recurrence as the Fibonacci numbers, but start with L(0) = 2
+/// and L(1) = 1.
+pub trait LucasFinder: FibFinder {
+    /// Finds the nth Fibonacci number and the nth Lucas number together.
+    fn fib_lucas_pair(&mut self, n: u64) -> (Integer, Integer);
+
+    /// Finds the nth Lucas number.
+    fn lucas(&mut self, n: u64) -> Integer {
+        self.fib_lucas_pair(n).1
+    }
+
+    /// Finds the nth Lucas number for any integer n, extending the sequence
+    /// backwards using L(-n) = (-1)^n L(n).
+    fn lucas_signed(&mut self, n: i64) -> Integer {
+        let l_n = self.lucas(n.unsigned_abs());
+        if n < 0 && n % 2 != 0 {
+            -l_n
+        } else {
+            l_n
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinetZ5, Cassini, CassiniGMP, MatExponentiator, GMP};
+
+    #[test]
+    fn test_lucas() {
+        let finders: Vec<Box<dyn LucasFinder>> = vec![
+            Box::new(MatExponentiator::default()),
+            Box::new(BinetZ5::default()),
+            Box::new(Cassini::default()),
+            Box::new(CassiniGMP::default()),
+            Box::new(GMP::default()),
+        ];
+        let first_lucas = [2u32, 1, 3, 4, 7, 11, 18, 29, 47, 76, 123];
+        for mut alg in finders {
+            for (n, l_n) in first_lucas.iter().enumerate() {
+                assert_eq!(alg.lucas(n as u64), *l_n);
+            }
+
+            assert_eq!(
+                alg.lucas(100),
+                "792070839848372253127".parse::<Integer>().unwrap()
+            );
+
+            for n in [0, 1, 2, 5, 64, 1000, 1001, 10000] {
+                let (f_n, l_n) = alg.fib_lucas_pair(n);
+                assert_eq!(f_n, alg.fib(n));
+                assert_eq!(l_n, Integer::from(Integer::lucas(n as u32)));
+                // F(2n) = F(n) L(n)
+                assert_eq!(alg.fib(2 * n), f_n * l_n);
+            }
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! The Lucas sequences U(P, Q) and V(P, Q), which follow the recurrence
+//! x(n) = P x(n - 1) - Q x(n - 2) starting from U(0) = 0, U(1) = 1 and V(0) = 2,
+//! V(1) = P. The Fibonacci and Lucas numbers are U and V for P = 1, Q = -1,
+//! and the same doubling tricks that make those fast work for any P and Q:
+//! U(2k) = U(k) V(k) and V(2k) = V(k)^2 - 2 Q^k, with a step of one more
+//! given by U(k + 1) = (P U(k) + V(k)) / 2 and V(k + 1) = (D U(k) + P V(k)) / 2,
+//! where D = P^2 - 4Q.
+
+use crate::{
+    bigint::{ops::RemRounding, Complete, Integer},
+    mat_exponentiator::{Mat2x2, ModMat2x2},
+    repeated_squaring::power,
+};
+
+/// The pair of Lucas sequences with parameters P and Q.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct LucasSequence {
+    p: Integer,
+    q: Integer,
+}
+
+impl LucasSequence {
+    /// The Lucas sequences with parameters P and Q.
+    pub fn new(p: impl Into<Integer>, q: impl Into<Integer>) -> Self {
+        LucasSequence {
+            p: p.into(),
+            q: q.into(),
+        }
+    }
+
+    /// P = 1, Q = -1: U is the Fibonacci numbers and V is the Lucas numbers.
+    pub fn fibonacci() -> Self {
+        LucasSequence::new(1, -1)
+    }
+
+    /// P = 2, Q = -1: U is the Pell numbers and V is the Pell-Lucas numbers.
+    pub fn pell() -> Self {
+        LucasSequence::new(2, -1)
+    }
+
+    /// P = 1, Q = -2: U is the Jacobsthal numbers and V is the
+    /// Jacobsthal-Lucas numbers.
+    pub fn jacobsthal() -> Self {
+        LucasSequence::new(1, -2)
+    }
+
+    /// P = 3, Q = 2: U is the Mersenne numbers 2^n - 1 and V is 2^n + 1.
+    pub fn mersenne() -> Self {
+        LucasSequence::new(3, 2)
+    }
+
+    /// The parameter P.
+    pub fn p(&self) -> &Integer {
+        &self.p
+    }
+
+    /// The parameter Q.
+    pub fn q(&self) -> &Integer {
+        &self.q
+    }
+
+    /// Finds U(n).
+    pub fn u(&self, n: u64) -> Integer {
+        self.uv(n).0
+    }
+
+    /// Finds V(n).
+    pub fn v(&self, n: u64) -> Integer {
+        self.uv(n).1
+    }
+
+    /// Finds U(n) and V(n) together.
+    pub fn uv(&self, n: u64) -> (Integer, Integer) {
+        if n == 0 {
+            return (0.into(), 2.into());
+        }
+
+        let d = self.p.square_ref().complete() - (&self.q << 2u32).complete();
+
+        let mut u_k = Integer::from(1);
+        let mut v_k = self.p.clone();
+        let mut q_k = self.q.clone();
+
+        let bits = format!("{:b}", n);
+        for b in bits.chars().skip(1) {
+            // U(2k) = U(k) V(k), V(2k) = V(k)^2 - 2 Q^k
+            u_k *= &v_k;
+            v_k = v_k.square() - (&q_k << 1u32).complete();
+            q_k.square_mut();
+
+            if b == '1' {
+                // both halves are always even, so the shifts are exact
+                let u_kp1 = (&self.p * &u_k).complete() + &v_k;
+                let v_kp1 = (&d * &u_k).complete() + (&self.p * &v_k).complete();
+                u_k = u_kp1 >> 1u32;
+                v_k = v_kp1 >> 1u32;
+                q_k *= &self.q;
+            }
+        }
+
+        (u_k, v_k)
+    }
+
+    /// Finds U(n) modulo m, as a value between 0 and m - 1.
+    pub fn u_mod(&self, n: u64, m: &Integer) -> Integer {
+        // the bottom left element of the nth power is U(n)
+        self.power_mod(n, m).c
+    }
+
+    /// Finds V(n) modulo m, as a value between 0 and m - 1.
+    pub fn v_mod(&self, n: u64, m: &Integer) -> Integer {
+        // V(n) = U(n + 1) - Q U(n - 1), which is the trace of the nth power
+        let ans = self.power_mod(n, m);
+        (ans.a + ans.d).rem_euc(m)
+    }
+
+    /// Raises the step matrix of the sequences to the nth power modulo m. This
+    /// avoids the halving in `uv`, which doesn't work for even m.
+    fn power_mod(&self, n: u64, m: &Integer) -> Mat2x2 {
+        let step = ModMat2x2::new(Mat2x2::lucas_sequence(&self.p, &self.q), m);
+        let ident = ModMat2x2::new(Mat2x2::identity(), m);
+        power(step, n, ident).mat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cassini, LucasFinder};
+
+    /// Checks the first terms of both sequences against the expected values.
+    fn check_start(seq: &LucasSequence, us: &[i64], vs: &[i64]) {
+        for (n, (u_n, v_n)) in us.iter().zip(vs).enumerate() {
+            assert_eq!(seq.uv(n as u64), (Integer::from(*u_n), Integer::from(*v_n)));
+        }
+    }
+
+    #[test]
+    fn test_fibonacci() {
+        let seq = LucasSequence::fibonacci();
+        check_start(
+            &seq,
+            &[0, 1, 1, 2, 3, 5, 8, 13, 21, 34, 55],
+            &[2, 1, 3, 4, 7, 11, 18, 29, 47, 76, 123],
+        );
+
+        let mut alg = Cassini::default();
+        for n in [100, 1000, 1001, 10000] {
+            assert_eq!(seq.uv(n), alg.fib_lucas_pair(n));
+        }
+    }
+
+    #[test]
+    fn test_pell() {
+        let seq = LucasSequence::pell();
+        check_start(
+            &seq,
+            &[0, 1, 2, 5, 12, 29, 70, 169, 408, 985, 2378],
+            &[2, 2, 6, 14, 34, 82, 198, 478, 1154, 2786, 6726],
+        );
+    }
+
+    #[test]
+    fn test_jacobsthal() {
+        let seq = LucasSequence::jacobsthal();
+        check_start(
+            &seq,
+            &[0, 1, 1, 3, 5, 11, 21, 43, 85, 171, 341],
+            &[2, 1, 5, 7, 17, 31, 65, 127, 257, 511, 1025],
+        );
+
+        // J(n) = (2^n - (-1)^n) / 3
+        let j_1000 = (Integer::from(Integer::u_pow_u(2, 1000)) - 1) / 3;
+        assert_eq!(seq.u(1000), j_1000);
+    }
+
+    #[test]
+    fn test_mersenne() {
+        let seq = LucasSequence::mersenne();
+        for n in [0, 1, 2, 3, 10, 63, 64, 127, 1000] {
+            let two_n = Integer::from(Integer::u_pow_u(2, n as u32));
+            assert_eq!(seq.u(n), (&two_n - 1u32).complete());
+            assert_eq!(seq.v(n), two_n + 1u32);
+        }
+    }
+
+    #[test]
+    fn test_recurrence() {
+        for p in -4..=4 {
+            for q in -4..=4 {
+                let seq = LucasSequence::new(p, q);
+                let (mut u_prev, mut u_curr) = (Integer::from(0), Integer::from(1));
+                let (mut v_prev, mut v_curr) = (Integer::from(2), Integer::from(p));
+                for n in 1..100 {
+                    assert_eq!(seq.uv(n), (u_curr.clone(), v_curr.clone()));
+                    let u_next = Integer::from(p * &u_curr) - Integer::from(q * &u_prev);
+                    let v_next = Integer::from(p * &v_curr) - Integer::from(q * &v_prev);
+                    (u_prev, u_curr) = (u_curr, u_next);
+                    (v_prev, v_curr) = (v_curr, v_next);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mod() {
+        let moduli = [1u64, 2, 10, 97, 1000, 1_000_000_007, u64::MAX];
+        for seq in [
+            LucasSequence::fibonacci(),
+            LucasSequence::pell(),
+            LucasSequence::jacobsthal(),
+            LucasSequence::mersenne(),
+            LucasSequence::new(-3, 5),
+        ] {
+            for m in moduli {
+                let m = Integer::from(m);
+                for n in [0, 1, 2, 3, 12, 37, 100, 1000, 1001] {
+                    let (u_n, v_n) = seq.uv(n);
+                    assert_eq!(seq.u_mod(n, &m), u_n.rem_euc(&m));
+                    assert_eq!(seq.v_mod(n, &m), v_n.rem_euc(&m));
+                }
+            }
+        }
+    }
+}
@@ -3,93 +3,363 @@
 //! entire sequence. Unpacks all matrix multiplications to avoid relying on a
 //! linear algebra library and reduce overhead.
 
-use std::ops::{Mul, MulAssign};
+use std::ops::{Add, Mul, MulAssign, Sub};
 
-use rug::Integer;
+use crate::{
+    bigint::{ops::RemRounding, Integer},
+    error::FibError,
+    fib_finder::{check_modulus, check_size, FibFinder},
+    lucas_finder::LucasFinder,
+    repeated_squaring::{power, power_integer},
+    ring::Ring,
+};
 
-use crate::{fib_finder::FibFinder, repeated_squaring::power};
-
-/// A simple 2x2 matrix with inlined multiplication and big integers.
+/// A simple 2x2 matrix with inlined multiplication, over big integers or any
+/// other ring.
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
-pub struct Mat2x2 {
+pub struct Mat2x2<T = Integer> {
     /// The top left element.
-    a: Integer,
+    pub(crate) a: T,
     /// The top right element.
-    b: Integer,
+    pub(crate) b: T,
     /// The bottom left element.
-    c: Integer,
+    pub(crate) c: T,
     /// The bottom right element.
-    d: Integer,
+    pub(crate) d: T,
 }
 
-impl Mat2x2 {
+impl<T: Ring> Mat2x2<T> {
+    /// The matrix ((a, b), (c, d)).
+    pub fn new(a: T, b: T, c: T, d: T) -> Self {
+        Mat2x2 { a, b, c, d }
+    }
+
+    /// Returns the element in row i and column j, counting from 0.
+    pub fn get(&self, i: usize, j: usize) -> &T {
+        match (i, j) {
+            (0, 0) => &self.a,
+            (0, 1) => &self.b,
+            (1, 0) => &self.c,
+            (1, 1) => &self.d,
+            _ => panic!("index ({}, {}) out of bounds for a 2x2 matrix", i, j),
+        }
+    }
+
+    /// Returns the matrix that steps the Lucas sequences with parameters P and
+    /// Q forward, ((P, -Q), (1, 0)). Its nth power is
+    /// ((U(n + 1), -Q U(n)), (U(n), -Q U(n - 1))). The Fibonacci matrix is the
+    /// case P = 1, Q = -1.
+    pub fn lucas_sequence(p: &T, q: &T) -> Self {
+        Mat2x2 {
+            a: p.clone(),
+            b: T::zero() - q,
+            c: T::one(),
+            d: T::zero(),
+        }
+    }
+
+    /// Returns the Fibonacci matrix, ((1, 1), (1, 0)).
+    pub fn fibonacci() -> Self {
+        Mat2x2::lucas_sequence(&T::one(), &(T::zero() - T::one()))
+    }
+
     /// Returns the identity element.
     pub fn identity() -> Self {
-        return Mat2x2 {
-            a: 1.into(),
-            b: 0.into(),
-            c: 0.into(),
-            d: 1.into(),
-        };
+        Mat2x2 {
+            a: T::one(),
+            b: T::zero(),
+            c: T::zero(),
+            d: T::one(),
+        }
     }
 }
 
-impl Mul<Mat2x2> for Mat2x2 {
+impl Mat2x2 {
+    /// Reduces every element modulo m, to a value between 0 and m - 1.
+    pub fn reduce(&mut self, m: &Integer) {
+        for x in [&mut self.a, &mut self.b, &mut self.c, &mut self.d] {
+            *x = std::mem::take(x).rem_euc(m);
+        }
+    }
+}
+
+impl<T: Ring> Mul<Mat2x2<T>> for Mat2x2<T> {
     type Output = Self;
 
     fn mul(self, rhs: Self) -> Self::Output {
+        self.mul_ref(&rhs)
+    }
+}
+
+impl<T: Ring> Mul<&Mat2x2<T>> for Mat2x2<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: &Self) -> Self::Output {
+        self.mul_ref(rhs)
+    }
+}
+
+/// Implements elementwise addition and subtraction, for both owned and
+/// borrowed right-hand sides.
+macro_rules! elementwise {
+    ($($tr:ident $f:ident),*) => {$(
+        impl<T: Ring> $tr<&Mat2x2<T>> for Mat2x2<T> {
+            type Output = Self;
+
+            fn $f(self, rhs: &Self) -> Self::Output {
+                Mat2x2 {
+                    a: self.a.$f(&rhs.a),
+                    b: self.b.$f(&rhs.b),
+                    c: self.c.$f(&rhs.c),
+                    d: self.d.$f(&rhs.d),
+                }
+            }
+        }
+
+        impl<T: Ring> $tr<Mat2x2<T>> for Mat2x2<T> {
+            type Output = Self;
+
+            fn $f(self, rhs: Self) -> Self::Output {
+                self.$f(&rhs)
+            }
+        }
+    )*};
+}
+
+elementwise!(Add add, Sub sub);
+
+/// 2x2 matrices over a ring are a ring themselves, though not a commutative
+/// one.
+impl<T: Ring> Ring for Mat2x2<T> {
+    fn from_small(x: u32) -> Self {
+        Mat2x2::new(T::from_small(x), T::zero(), T::zero(), T::from_small(x))
+    }
+
+    fn mul_ref(&self, rhs: &Self) -> Self {
         // (a b) (a' b') => (aa' + bc' ab' + bd')
         // (c d) (c' d') => (ca' + dc' cb' + dd')
-        return Mat2x2 {
-            a: Integer::from(&self.a * &rhs.a) + Integer::from(&self.b * &rhs.c),
-            b: Integer::from(&self.a * &rhs.b) + Integer::from(&self.b * &rhs.d),
-            c: Integer::from(&self.c * &rhs.a) + Integer::from(&self.d * &rhs.c),
-            d: Integer::from(&self.c * &rhs.b) + Integer::from(&self.d * &rhs.d),
-        };
+        Mat2x2 {
+            a: self.a.mul_ref(&rhs.a) + self.b.mul_ref(&rhs.c),
+            b: self.a.mul_ref(&rhs.b) + self.b.mul_ref(&rhs.d),
+            c: self.c.mul_ref(&rhs.a) + self.d.mul_ref(&rhs.c),
+            d: self.c.mul_ref(&rhs.b) + self.d.mul_ref(&rhs.d),
+        }
     }
 }
 
-impl MulAssign<Mat2x2> for Mat2x2 {
-    fn mul_assign(&mut self, rhs: Mat2x2) {
-        *self = self.clone() * rhs;
+impl<T: Ring> MulAssign<Mat2x2<T>> for Mat2x2<T> {
+    fn mul_assign(&mut self, rhs: Mat2x2<T>) {
+        *self = self.mul_ref(&rhs);
     }
 }
 
-impl Mul<(Integer, Integer)> for Mat2x2 {
-    type Output = (Integer, Integer);
+impl<T: Ring> Mul<(T, T)> for Mat2x2<T> {
+    type Output = (T, T);
 
-    fn mul(self, rhs: (Integer, Integer)) -> Self::Output {
+    fn mul(self, rhs: (T, T)) -> Self::Output {
         let (x, y) = rhs;
+        (self.a * &x + self.b * &y, self.c * &x + self.d * &y)
+    }
+}
+
+/// A 2x2 matrix whose elements are reduced modulo a fixed modulus after every
+/// multiplication, so they never grow past the size of the modulus.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub(crate) struct ModMat2x2 {
+    pub(crate) mat: Mat2x2,
+    modulus: Integer,
+}
+
+impl ModMat2x2 {
+    /// Reduces mat modulo m.
+    pub(crate) fn new(mut mat: Mat2x2, m: &Integer) -> Self {
+        mat.reduce(m);
+        ModMat2x2 {
+            mat,
+            modulus: m.clone(),
+        }
+    }
+
+    /// Returns the Fibonacci matrix and the identity, both reduced modulo m.
+    fn fib_and_identity(m: &Integer) -> (Self, Self) {
         (
-            Integer::from(&self.a * &x) + Integer::from(&self.b * &y),
-            Integer::from(&self.c * &x) + Integer::from(&self.d * &y),
+            ModMat2x2::new(Mat2x2::fibonacci(), m),
+            ModMat2x2::new(Mat2x2::identity(), m),
         )
     }
 }
 
+impl MulAssign<ModMat2x2> for ModMat2x2 {
+    fn mul_assign(&mut self, rhs: ModMat2x2) {
+        self.mat *= rhs.mat;
+        self.mat.reduce(&self.modulus);
+    }
+}
+
+/// A square matrix of any size with big integer elements, for recurrences that
+/// need more than two terms of state.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct MatNxN {
+    /// The number of rows and columns.
+    size: usize,
+    /// The elements in row-major order.
+    elems: Vec<Integer>,
+}
+
+impl MatNxN {
+    /// Returns the size x size identity matrix.
+    pub fn identity(size: usize) -> Self {
+        let mut elems = vec![Integer::new(); size * size];
+        for i in 0..size {
+            elems[i * size + i] = 1.into();
+        }
+        MatNxN { size, elems }
+    }
+
+    /// Makes a matrix from its rows, which must all be as long as there are
+    /// rows.
+    pub fn from_rows(rows: Vec<Vec<Integer>>) -> Self {
+        let size = rows.len();
+        assert!(
+            rows.iter().all(|row| row.len() == size),
+            "matrix must be square"
+        );
+        MatNxN {
+            size,
+            elems: rows.into_iter().flatten().collect(),
+        }
+    }
+
+    /// Returns the companion matrix of the recurrence
+    /// x(n) = c(1) x(n - 1) + ... + c(k) x(n - k), given c(1), ..., c(k). It
+    /// takes (x(n + k - 1), ..., x(n)) to (x(n + k), ..., x(n + 1)).
+    pub fn companion(coeffs: &[Integer]) -> Self {
+        let size = coeffs.len();
+        let mut mat = MatNxN {
+            size,
+            elems: vec![Integer::new(); size * size],
+        };
+        mat.elems[..size].clone_from_slice(coeffs);
+        for i in 1..size {
+            mat.elems[i * size + i - 1] = 1.into();
+        }
+        mat
+    }
+
+    /// The number of rows and columns.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The element in row i and column j.
+    pub fn get(&self, i: usize, j: usize) -> &Integer {
+        &self.elems[i * self.size + j]
+    }
+}
+
+impl Mul<&MatNxN> for &MatNxN {
+    type Output = MatNxN;
+
+    fn mul(self, rhs: &MatNxN) -> Self::Output {
+        assert_eq!(self.size, rhs.size, "matrix sizes must match");
+        let size = self.size;
+        let mut elems = vec![Integer::new(); size * size];
+        for i in 0..size {
+            for k in 0..size {
+                let x = self.get(i, k);
+                if *x == 0 {
+                    continue;
+                }
+                for j in 0..size {
+                    elems[i * size + j] += x * rhs.get(k, j);
+                }
+            }
+        }
+        MatNxN { size, elems }
+    }
+}
+
+impl MulAssign<MatNxN> for MatNxN {
+    fn mul_assign(&mut self, rhs: MatNxN) {
+        *self = &*self * &rhs;
+    }
+}
+
 /// Matrix exponentiation approach using repeated squaring.
 #[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
 pub struct MatExponentiator {}
 
-impl FibFinder for MatExponentiator {
-    fn fib(&mut self, n: u64) -> Integer {
-        let fib_mat = Mat2x2 {
-            a: 1.into(),
-            b: 1.into(),
-            c: 1.into(),
-            d: 0.into(),
-        };
+impl MatExponentiator {
+    /// The biggest index `try_fib` takes. Every step multiplies out two full
+    /// matrices, four times the squarings the doubling algorithms need, so
+    /// past this it falls well behind them.
+    pub const MAX_INDEX: u64 = 25_600_000;
+
+    /// Finds the nth Fibonacci number in any ring, like F(n) mod 2^128 with
+    /// `Wrapping<u128>`.
+    pub fn fib_ring<T: Ring>(&mut self, n: u64) -> T {
+        let fib_mat = Mat2x2::fibonacci();
         // dbg!(power(fib_mat.clone(), 3, Mat2x2::identity()));
-        let (fib_curr, _fib_prev) = power(fib_mat, n, Mat2x2::identity()) * (0.into(), 1.into());
+        let (fib_curr, _fib_prev) = power(fib_mat, n, Mat2x2::identity()) * (T::zero(), T::one());
         fib_curr
     }
 }
 
+impl FibFinder for MatExponentiator {
+    fn fib(&mut self, n: u64) -> Integer {
+        self.fib_ring(n)
+    }
+
+    fn try_fib(&mut self, n: u64) -> Result<Integer, FibError> {
+        check_size(n)?;
+        if n > Self::MAX_INDEX {
+            return Err(FibError::UnsupportedRange {
+                n,
+                max: Self::MAX_INDEX,
+            });
+        }
+        Ok(self.fib(n))
+    }
+
+    fn fib_pair(&mut self, n: u64) -> (Integer, Integer) {
+        let fib_mat = Mat2x2::fibonacci();
+        // the nth power is ((F(n + 1), F(n)), (F(n), F(n - 1)))
+        let ans = power(fib_mat, n, Mat2x2::identity());
+        (ans.b, ans.a)
+    }
+
+    fn fib_mod(&mut self, n: u64, m: &Integer) -> Integer {
+        check_modulus(m);
+        let (fib_mat, ident) = ModMat2x2::fib_and_identity(m);
+        let ans = power(fib_mat, n, ident);
+        // the top right element of the nth power is F(n)
+        ans.mat.b
+    }
+
+    fn fib_mod_big(&mut self, n: &Integer, m: &Integer) -> Integer {
+        check_modulus(m);
+        let (fib_mat, ident) = ModMat2x2::fib_and_identity(m);
+        let ans = power_integer(fib_mat, n, ident);
+        ans.mat.b
+    }
+}
+
+impl LucasFinder for MatExponentiator {
+    fn fib_lucas_pair(&mut self, n: u64) -> (Integer, Integer) {
+        let fib_mat = Mat2x2::fibonacci();
+        // the nth power is ((F(n + 1), F(n)), (F(n), F(n - 1))), and
+        // L(n) = F(n + 1) + F(n - 1)
+        let ans = power(fib_mat, n, Mat2x2::identity());
+        (ans.b, ans.a + ans.d)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use rug::ops::Pow;
+    use crate::bigint::ops::Pow;
 
     use super::*;
+    use crate::{Cassini, Zmod};
 
     #[test]
     fn test_fib() {
@@ -126,4 +396,49 @@ mod tests {
             8242546875_u64
         );
     }
+
+    #[test]
+    fn test_try_fib() {
+        let mut alg = MatExponentiator::default();
+        assert_eq!(alg.try_fib(1000), Ok(alg.fib(1000)));
+        assert_eq!(
+            alg.try_fib(30_000_000),
+            Err(FibError::UnsupportedRange {
+                n: 30_000_000,
+                max: MatExponentiator::MAX_INDEX
+            })
+        );
+    }
+
+    #[test]
+    fn test_mat_ring() {
+        const M: u64 = 1_000_000_007;
+        let mut alg = MatExponentiator::default();
+        let m = Integer::from(M);
+        let fib_mod = |n| Zmod::<M>::new(Cassini::default().fib_mod(n, &m).to_u64().unwrap());
+        for n in [1, 2, 3, 12, 100, 1000, 1001, u64::MAX] {
+            // ((1, 1), (1, 0))^n = ((F(n + 1), F(n)), (F(n), F(n - 1)))
+            let mat = Mat2x2::<Zmod<M>>::fibonacci().pow(n);
+            assert_eq!(*mat.get(0, 1), fib_mod(n));
+            assert_eq!(*mat.get(1, 0), fib_mod(n));
+            assert_eq!(*mat.get(1, 1), fib_mod(n - 1));
+
+            // over a matrix ring, F(n) is F(n) times the identity
+            let f_n = alg.fib_ring::<Mat2x2<Zmod<M>>>(n);
+            assert_eq!(
+                f_n,
+                Mat2x2::new(fib_mod(n), Zmod::new(0), Zmod::new(0), fib_mod(n))
+            );
+        }
+
+        // not commutative
+        let x = Mat2x2::new(1.into(), 2.into(), 3.into(), 4.into());
+        let y = Mat2x2::<Integer>::fibonacci();
+        assert_ne!(x.clone() * &y, y.clone() * &x);
+        assert_eq!(x.clone() - &x, Mat2x2::zero());
+        assert_eq!(
+            x.clone() + &Mat2x2::one(),
+            Mat2x2::new(2.into(), 2.into(), 3.into(), 5.into())
+        );
+    }
 }
@@ -1,28 +1,24 @@
 //! Recursive Fibonacci implementation that uses memoization to cause an exponential speedup.
 
-use rug::Integer;
 use std::collections::HashMap;
 
-use crate::fib_finder::FibFinder;
+use crate::{bigint::Integer, error::FibError, fib_finder::FibFinder};
 
 /// Recursive approach, using the basic definition of Fibonacci numbers: F(0) =
 /// 0, F(1) = 1, and F(n) = F(n - 1) + F(n - 2). The difference from the naïve
 /// approach is that we *memoize* results, caching them to avoid duplicating
 /// work.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct MemoizedRecursor {
-    results: HashMap<u64, rug::Integer>,
-}
-
-impl Default for MemoizedRecursor {
-    fn default() -> Self {
-        Self {
-            results: HashMap::new(),
-        }
-    }
+    results: HashMap<u64, Integer>,
 }
 
 impl MemoizedRecursor {
+    /// The biggest index `try_fib` takes, unless it's already cached. The first
+    /// call goes as deep into the recursion as the index, so much more than
+    /// this risks overflowing the stack.
+    pub const MAX_INDEX: u64 = 1000;
+
     /// Clears the cache, ensuring that no work is saved from previous runs.
     pub fn clear(&mut self) {
         self.results = HashMap::new();
@@ -45,6 +41,16 @@ impl FibFinder for MemoizedRecursor {
             result
         }
     }
+
+    fn try_fib(&mut self, n: u64) -> Result<Integer, FibError> {
+        if n > Self::MAX_INDEX && !self.results.contains_key(&n) {
+            return Err(FibError::UnsupportedRange {
+                n,
+                max: Self::MAX_INDEX,
+            });
+        }
+        Ok(self.fib(n))
+    }
 }
 
 #[cfg(test)]
@@ -86,4 +92,17 @@ mod tests {
         // alg.clear();
         // assert_eq!(alg.fib(10000) % (Integer::from(10).pow(10)), 9947366875_u64);
     }
+
+    #[test]
+    fn test_try_fib() {
+        let mut alg = MemoizedRecursor::default();
+        assert_eq!(alg.try_fib(37), Ok(Integer::from(24157817)));
+        assert_eq!(
+            alg.try_fib(10000),
+            Err(FibError::UnsupportedRange {
+                n: 10000,
+                max: MemoizedRecursor::MAX_INDEX
+            })
+        );
+    }
 }
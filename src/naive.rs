@@ -1,12 +1,18 @@
 //! Naïve approach to computing Fibonacci numbers: simple recursion.
-use rug::Integer;
 
-use crate::fib_finder::FibFinder;
+use crate::{bigint::Integer, error::FibError, fib_finder::FibFinder};
 
 /// Naïve recursive approach, using the basic definition of Fibonacci numbers: F(0) = 0, F(1) = 1, and F(n) = F(n - 1) + F(n - 2).
 #[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
 pub struct NaiveRecursor {}
 
+impl NaiveRecursor {
+    /// The biggest index `try_fib` takes. The recursion makes about F(n) calls,
+    /// which already takes seconds at 40 and doubles with every two steps
+    /// after that.
+    pub const MAX_INDEX: u64 = 40;
+}
+
 impl FibFinder for NaiveRecursor {
     fn fib(&mut self, n: u64) -> Integer {
         match n {
@@ -15,6 +21,16 @@ impl FibFinder for NaiveRecursor {
             _ => self.fib(n - 1) + self.fib(n - 2),
         }
     }
+
+    fn try_fib(&mut self, n: u64) -> Result<Integer, FibError> {
+        if n > Self::MAX_INDEX {
+            return Err(FibError::UnsupportedRange {
+                n,
+                max: Self::MAX_INDEX,
+            });
+        }
+        Ok(self.fib(n))
+    }
 }
 
 #[cfg(test)]
@@ -50,4 +66,18 @@ mod tests {
         //         .unwrap()
         // );
     }
+
+    #[test]
+    fn test_try_fib() {
+        let mut alg = NaiveRecursor::default();
+        assert_eq!(alg.try_fib(12), Ok(Integer::from(144)));
+        assert_eq!(
+            alg.try_fib(41),
+            Err(FibError::UnsupportedRange {
+                n: 41,
+                max: NaiveRecursor::MAX_INDEX
+            })
+        );
+        assert!(alg.try_fib(1_000_000).is_err());
+    }
 }
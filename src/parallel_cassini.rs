@@ -0,0 +1,151 @@
+//! The same doubling as `CassiniGMP`, but with the two squarings in each step
+//! run on separate threads. They don't depend on each other, and for huge n
+//! they're where almost all of the time goes, so with two cores to spare the
+//! top of the range takes not much more than half as long. A single helper
+//! thread takes one of the two squarings in every step, and it's only started
+//! once the numbers are big enough for handing it work to pay off: before
+//! that, the steps stay on the calling thread.
+
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, Scope},
+};
+
+use crate::{
+    bigint::{Complete, Integer},
+    cassini_gmp::double_from_with,
+    fib_finder::FibFinder,
+};
+
+/// Squarings of numbers smaller than this many bits aren't worth handing to
+/// the helper thread. A handoff costs around 5µs, so this is about where a
+/// squaring takes ten times that: num-bigint gets there at 2^15 bits, and
+/// GMP, which is faster at these sizes, closer to 2^16.
+#[cfg(feature = "rug")]
+const MIN_PARALLEL_BITS: u32 = 1 << 16;
+#[cfg(not(feature = "rug"))]
+const MIN_PARALLEL_BITS: u32 = 1 << 15;
+
+/// Cassini's identity recursion approach, squaring on two threads at once.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub struct ParallelCassini {}
+
+impl FibFinder for ParallelCassini {
+    fn fib(&mut self, n: u64) -> Integer {
+        if n < 2 {
+            return n.into();
+        }
+
+        fib2(n).0
+    }
+
+    fn fib_pair(&mut self, n: u64) -> (Integer, Integer) {
+        if n == 0 {
+            return (0.into(), 1.into());
+        }
+
+        let (f_n, f_nm1) = fib2(n);
+        let f_np1 = (&f_n + &f_nm1).complete();
+        (f_n, f_np1)
+    }
+}
+
+/// Finds F(n) and F(n - 1) for n at least 1.
+fn fib2(n: u64) -> (Integer, Integer) {
+    let parallel = thread::available_parallelism().is_ok_and(|cores| cores.get() > 1);
+    let top_bit = 63 - n.leading_zeros();
+    thread::scope(|scope| {
+        let mut helper = None;
+        double_from_with(
+            Integer::from(1u32),
+            Integer::from(0u32),
+            true,
+            (0..top_bit).rev().map(|i| (n >> i) & 1 == 1),
+            move |f_i, f_im1| {
+                if parallel && f_im1.significant_bits() >= MIN_PARALLEL_BITS {
+                    helper
+                        .get_or_insert_with(|| Squarer::spawn(scope))
+                        .square_both(f_i, f_im1)
+                } else {
+                    (f_i.square(), f_im1.square())
+                }
+            },
+        )
+    })
+}
+
+/// A thread that squares whatever it's sent, so the doubling steps can share
+/// one instead of each starting their own.
+struct Squarer {
+    jobs: Sender<Integer>,
+    squares: Receiver<Integer>,
+}
+
+impl Squarer {
+    /// Starts the thread, which stops once the `Squarer` is dropped.
+    fn spawn<'scope>(scope: &'scope Scope<'scope, '_>) -> Self {
+        let (jobs, job_rx) = mpsc::channel::<Integer>();
+        let (square_tx, squares) = mpsc::channel();
+        scope.spawn(move || {
+            for a in job_rx {
+                if square_tx.send(a.square()).is_err() {
+                    break;
+                }
+            }
+        });
+        Squarer { jobs, squares }
+    }
+
+    /// Squares a on the helper thread and b on this one.
+    fn square_both(&self, a: Integer, b: Integer) -> (Integer, Integer) {
+        self.jobs.send(a).unwrap();
+        let b_sqr = b.square();
+        (self.squares.recv().unwrap(), b_sqr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bigint::{ops::Pow, TEST_HUGE_INDICES};
+
+    use super::*;
+    use crate::CassiniGMP;
+
+    #[test]
+    fn test_fib() {
+        let mut alg = ParallelCassini::default();
+        let mut reference = CassiniGMP::default();
+        for n in (0..100).chain([1000, 1001, 10000]) {
+            assert_eq!(alg.fib(n), reference.fib(n));
+            assert_eq!(alg.fib_pair(n), reference.fib_pair(n));
+        }
+
+        // F(n) has about 0.69 n bits, so the last couple of steps here are big
+        // enough to go to the helper thread when there's a core for it
+        let n = 4 * u64::from(MIN_PARALLEL_BITS);
+        assert_eq!(alg.fib(n), reference.fib(n));
+        assert_eq!(alg.fib_pair(n + 1), reference.fib_pair(n + 1));
+
+        if TEST_HUGE_INDICES {
+            assert_eq!(
+                alg.fib(10_000_000) % (Integer::from(10).pow(10)),
+                6380546875_u64
+            );
+        }
+    }
+
+    #[test]
+    fn test_squarer() {
+        thread::scope(|scope| {
+            let squarer = Squarer::spawn(scope);
+            for k in [1, 100, 100_000] {
+                let a = Integer::from(Integer::u_pow_u(3, k));
+                let b = Integer::from(Integer::u_pow_u(7, k));
+                assert_eq!(
+                    squarer.square_both(a.clone(), b.clone()),
+                    (a.square_ref().complete(), b.square_ref().complete())
+                );
+            }
+        });
+    }
+}
@@ -0,0 +1,278 @@
+//! Computes Pisano periods: the period of the Fibonacci sequence modulo m.
+//! Rather than walking the sequence until it repeats, which takes up to 6m
+//! steps, we factor m, work out the period modulo each prime from the divisors
+//! of p - 1 or 2(p + 1), lift those to prime powers, and take the least common
+//! multiple. F(n) mod m only depends on n modulo the period, so the default
+//! `fib_mod_big` uses it to cut indices with thousands of bits down to about
+//! the size of m.
+
+use crate::{
+    bigint::{ops::Pow, Integer},
+    cassini::Cassini,
+    fib_finder::FibFinder,
+};
+
+/// Returns the Pisano period of m: the smallest k > 0 such that F(k) and
+/// F(k + 1) are 0 and 1 mod m. This relies on the period of p^e being p^(e - 1)
+/// times the period of p, which is true for every prime that has been checked.
+pub fn pisano_period(m: u64) -> Integer {
+    assert!(m > 0, "modulus must be positive");
+
+    let mut period = Integer::from(1);
+    for (p, e) in factor(m) {
+        let prime_power_period = prime_period(p) * Integer::from(p).pow(e - 1);
+        period = period.lcm(&prime_power_period);
+    }
+
+    period
+}
+
+/// Returns the Pisano period of the prime p.
+fn prime_period(p: u64) -> Integer {
+    match p {
+        2 => return 3.into(),
+        5 => return 20.into(),
+        _ => {}
+    }
+
+    // the period divides p - 1 if 5 is a square mod p, and 2(p + 1) otherwise,
+    // so start there and divide out as many primes as we can
+    let (mut period, factors) = if p % 5 == 1 || p % 5 == 4 {
+        (Integer::from(p - 1), factor(p - 1))
+    } else {
+        // p + 1 is even, so 2 is already one of its factors
+        (Integer::from(p + 1) * 2, factor(p + 1))
+    };
+
+    let modulus = Integer::from(p);
+    for (q, _) in factors {
+        let q = Integer::from(q);
+        while period.is_divisible(&q) {
+            let smaller = period.clone().div_exact(&q);
+            if !is_period(&smaller, &modulus) {
+                break;
+            }
+            period = smaller;
+        }
+    }
+
+    period
+}
+
+/// Checks whether the Fibonacci sequence mod m repeats after k steps.
+fn is_period(k: &Integer, m: &Integer) -> bool {
+    let mut alg = Cassini::default();
+    alg.fib_mod_big(k, m) == 0 && alg.fib_mod_big(&Integer::from(k + 1), m) == 1
+}
+
+/// Factors n into primes, returning each prime and its exponent in increasing
+/// order of the primes.
+fn factor(n: u64) -> Vec<(u64, u32)> {
+    let mut n = n;
+    let mut primes = Vec::new();
+
+    // trial division gets rid of the small factors cheaply
+    let mut d = 2;
+    while d < 1000 && d * d <= n {
+        while n % d == 0 {
+            primes.push(d);
+            n /= d;
+        }
+        d += 1;
+    }
+    split(n, &mut primes);
+    primes.sort_unstable();
+
+    let mut factors: Vec<(u64, u32)> = Vec::new();
+    for p in primes {
+        match factors.last_mut() {
+            Some((last, e)) if *last == p => *e += 1,
+            _ => factors.push((p, 1)),
+        }
+    }
+
+    factors
+}
+
+/// Adds the prime factors of n to primes, using Pollard's rho to split it.
+fn split(n: u64, primes: &mut Vec<u64>) {
+    if n == 1 {
+        return;
+    }
+    if is_prime(n) {
+        primes.push(n);
+        return;
+    }
+    let d = pollard_rho(n);
+    split(d, primes);
+    split(n / d, primes);
+}
+
+/// Computes a * b mod m without overflowing.
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 * b as u128) % m as u128) as u64
+}
+
+/// Computes base^exp mod m.
+fn pow_mod(base: u64, exp: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    let mut base = base % m;
+    let mut exp = exp;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Miller-Rabin primality test. These bases are enough to make it
+/// deterministic for every u64.
+fn is_prime(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    for p in BASES {
+        if n % p == 0 {
+            return n == p;
+        }
+    }
+
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    'bases: for a in BASES {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+
+    true
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Finds a nontrivial factor of the composite number n.
+fn pollard_rho(n: u64) -> u64 {
+    if n % 2 == 0 {
+        return 2;
+    }
+    for c in 1..n {
+        let f = |x: u64| ((mul_mod(x, x, n) as u128 + c as u128) % n as u128) as u64;
+        let (mut x, mut y, mut d) = (2, 2, 1);
+        while d == 1 {
+            x = f(x);
+            y = f(f(y));
+            d = gcd(x.abs_diff(y), n);
+        }
+        // if the cycle closed without finding a factor, try another polynomial
+        if d != n {
+            return d;
+        }
+    }
+    unreachable!("{} is prime", n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MatExponentiator;
+
+    /// Finds the period by walking the sequence until it gets back to 0, 1.
+    fn brute_force_period(m: u64) -> u64 {
+        let (mut prev, mut curr) = (0, 1 % m);
+        let mut k = 0;
+        loop {
+            let new = (prev + curr) % m;
+            prev = curr;
+            curr = new;
+            k += 1;
+            if prev == 0 && curr == 1 % m {
+                return k;
+            }
+        }
+    }
+
+    #[test]
+    fn test_factor() {
+        assert_eq!(factor(1), Vec::<(u64, u32)>::new());
+        assert_eq!(factor(2), vec![(2, 1)]);
+        assert_eq!(factor(360), vec![(2, 3), (3, 2), (5, 1)]);
+        assert_eq!(
+            factor(u64::MAX),
+            vec![
+                (3, 1),
+                (5, 1),
+                (17, 1),
+                (257, 1),
+                (641, 1),
+                (65537, 1),
+                (6700417, 1)
+            ]
+        );
+        assert_eq!(
+            factor(1_000_000_007 * 999_999_937),
+            vec![(999_999_937, 1), (1_000_000_007, 1)]
+        );
+        assert_eq!(factor(1_000_003 * 1_000_003), vec![(1_000_003, 2)]);
+    }
+
+    #[test]
+    fn test_pisano_period() {
+        for m in 1..=1000 {
+            assert_eq!(pisano_period(m), brute_force_period(m));
+        }
+
+        assert_eq!(pisano_period(1_000_000_007), 2_000_000_016u64);
+        assert_eq!(pisano_period(999_999_937), 285_714_268u64);
+        assert_eq!(pisano_period(1_000_000_000), 1_500_000_000u64);
+        assert_eq!(pisano_period(2_147_483_647), 4_294_967_296u64);
+        assert_eq!(pisano_period(123_456_789), 6_862_416u64);
+        assert_eq!(pisano_period(10_000_000_000), 15_000_000_000u64);
+    }
+
+    #[test]
+    fn test_index_mod_period() {
+        let mut alg = Cassini::default();
+        let googol = Integer::from(Integer::u_pow_u(10, 100));
+        for m in [2, 10, 997, 1000, 1_000_000_007, 10_000_000_000] {
+            let period = pisano_period(m);
+            let m = Integer::from(m);
+            for n in [googol.clone(), googol.clone() + 1u32, googol.clone() * 7u32] {
+                let reduced = Integer::from(&n % &period);
+                assert_eq!(alg.fib_mod_big(&n, &m), alg.fib_mod_big(&reduced, &m));
+            }
+        }
+    }
+
+    #[test]
+    fn test_fib_mod_big_reduces() {
+        // big enough for the default fib_mod_big to reduce by the period, which
+        // MatExponentiator's own never does
+        let mut alg = Cassini::default();
+        let mut reference = MatExponentiator::default();
+        let n = Integer::from(Integer::u_pow_u(10, 10_000));
+        for m in [1, 2, 10, 1_000_000_007, u64::MAX] {
+            let m = Integer::from(m);
+            for n in [n.clone(), n.clone() + 1u32] {
+                assert_eq!(alg.fib_mod_big(&n, &m), reference.fib_mod_big(&n, &m));
+            }
+        }
+    }
+}
@@ -0,0 +1,673 @@
+//! A stand-in for the parts of `rug`'s `Integer` the crate uses, built on
+//! `num-bigint`, for building without GMP. rug evaluates expressions like
+//! `&a + &b` lazily, and `complete()` or `Integer::from` turns them into an
+//! `Integer`. Here they're evaluated right away, but they still come back
+//! wrapped as `Incomplete` so that code written against one backend compiles
+//! against the other.
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    iter::Sum,
+    ops::{
+        Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Shl, ShlAssign, Shr,
+        ShrAssign, Sub, SubAssign,
+    },
+    str::FromStr,
+};
+
+use num_bigint::{BigInt, ParseBigIntError, Sign};
+use num_integer::Integer as _;
+use num_traits::{Num, Signed, ToPrimitive, Zero};
+
+/// The operator traits rug adds on top of the standard ones.
+pub mod ops {
+    /// Raising to a power.
+    pub trait Pow<Rhs> {
+        /// The type of the result.
+        type Output;
+        /// Raises self to the power rhs.
+        fn pow(self, rhs: Rhs) -> Self::Output;
+    }
+
+    /// Remainders of division rounded in different ways.
+    pub trait RemRounding<Rhs = Self> {
+        /// The type of the result.
+        type Output;
+        /// The remainder of Euclidean division, which is never negative.
+        fn rem_euc(self, rhs: Rhs) -> Self::Output;
+    }
+}
+
+/// Finishes computing a value, like rug's `Complete`.
+pub trait Complete {
+    /// The type of the finished value.
+    type Completed;
+    /// Finishes computing the value.
+    fn complete(self) -> Self::Completed;
+}
+
+/// Assigns to a value in place, like rug's `Assign`.
+pub trait Assign<Src = Self> {
+    /// Replaces self with src.
+    fn assign(&mut self, src: Src);
+}
+
+/// An arbitrary-precision integer.
+#[derive(Clone, Default, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Integer(BigInt);
+
+/// The result of an operation on borrowed integers, where rug would give back
+/// an incomplete computation.
+#[derive(Clone, Debug)]
+pub struct Incomplete(BigInt);
+
+/// Two results at once, from `Integer::fibonacci_2`.
+#[derive(Clone, Debug)]
+pub struct IncompletePair(BigInt, BigInt);
+
+impl Integer {
+    /// Zero.
+    pub fn new() -> Self {
+        Integer(BigInt::zero())
+    }
+
+    /// F(n). GMP has a dedicated function for this, and this stands in for
+    /// it with plain doubling.
+    pub fn fibonacci(n: u32) -> Incomplete {
+        Incomplete(fibonacci_2(n).0)
+    }
+
+    /// F(n) and F(n - 1), where F(-1) = 1.
+    pub fn fibonacci_2(n: u32) -> IncompletePair {
+        let (f_n, f_nm1) = fibonacci_2(n);
+        IncompletePair(f_n, f_nm1)
+    }
+
+    /// The nth Lucas number.
+    pub fn lucas(n: u32) -> Incomplete {
+        // L(n) = F(n) + 2 F(n - 1)
+        let (f_n, f_nm1) = fibonacci_2(n);
+        Incomplete(f_n + (f_nm1 << 1u32))
+    }
+
+    /// base to the power exp.
+    pub fn u_pow_u(base: u32, exp: u32) -> Incomplete {
+        Incomplete(num_traits::Pow::pow(BigInt::from(base), exp))
+    }
+
+    /// The square of self.
+    pub fn square_ref(&self) -> Incomplete {
+        Incomplete(&self.0 * &self.0)
+    }
+
+    /// The square of self.
+    pub fn square(self) -> Self {
+        Integer(&self.0 * &self.0)
+    }
+
+    /// Squares self in place.
+    pub fn square_mut(&mut self) {
+        self.0 = &self.0 * &self.0;
+    }
+
+    /// The absolute value of self.
+    pub fn abs_ref(&self) -> Incomplete {
+        Incomplete(self.0.abs())
+    }
+
+    /// The absolute value of self.
+    pub fn abs(self) -> Self {
+        Integer(self.0.abs())
+    }
+
+    /// The least common multiple of self and other.
+    pub fn lcm(self, other: &Integer) -> Self {
+        Integer(self.0.lcm(&other.0))
+    }
+
+    /// Whether self is a multiple of divisor.
+    pub fn is_divisible(&self, divisor: &Integer) -> bool {
+        if divisor.0.is_zero() {
+            self.0.is_zero()
+        } else {
+            self.0.is_multiple_of(&divisor.0)
+        }
+    }
+
+    /// self divided by divisor, which must divide it exactly.
+    pub fn div_exact(self, divisor: &Integer) -> Self {
+        debug_assert!(self.is_divisible(divisor));
+        Integer(self.0 / &divisor.0)
+    }
+
+    /// self to the power exponent mod modulus, from 0 to |modulus| - 1. A
+    /// negative exponent takes the inverse first, and fails if there isn't
+    /// one.
+    pub fn pow_mod(self, exponent: &Integer, modulus: &Integer) -> Result<Self, Self> {
+        let m = modulus.0.abs();
+        let base = if exponent.0.is_negative() {
+            let e = self.0.extended_gcd(&m);
+            if e.gcd != BigInt::from(1) {
+                return Err(self);
+            }
+            e.x
+        } else {
+            self.0
+        };
+        Ok(Integer(base.mod_floor(&m).modpow(&exponent.0.abs(), &m)))
+    }
+
+    /// The number of bits in the absolute value, not counting leading zeros.
+    pub fn significant_bits(&self) -> u32 {
+        self.0.bits() as u32
+    }
+
+    /// Bit i of the two's complement representation.
+    pub fn get_bit(&self, i: u32) -> bool {
+        self.0.bit(i.into())
+    }
+
+    /// Whether self is odd.
+    pub fn is_odd(&self) -> bool {
+        self.0.is_odd()
+    }
+
+    /// Whether self is even.
+    pub fn is_even(&self) -> bool {
+        self.0.is_even()
+    }
+
+    /// How self compares to zero.
+    pub fn cmp0(&self) -> Ordering {
+        self.0.sign().cmp(&Sign::NoSign)
+    }
+
+    /// self as a u32, if it fits.
+    pub fn to_u32(&self) -> Option<u32> {
+        self.0.to_u32()
+    }
+
+    /// self as a u64, if it fits.
+    pub fn to_u64(&self) -> Option<u64> {
+        self.0.to_u64()
+    }
+
+    /// self as an i64, if it fits.
+    pub fn to_i64(&self) -> Option<i64> {
+        self.0.to_i64()
+    }
+
+    /// self as an f64, rounded.
+    pub fn to_f64(&self) -> f64 {
+        self.0.to_f64().unwrap_or(f64::NAN)
+    }
+
+    /// self in the given radix, from 2 to 36.
+    pub fn to_string_radix(&self, radix: i32) -> String {
+        self.0.to_str_radix(radix as u32)
+    }
+
+    /// Parses an integer in the given radix, from 2 to 36.
+    pub fn from_str_radix(src: &str, radix: i32) -> Result<Self, ParseBigIntError> {
+        BigInt::from_str_radix(src, radix as u32).map(Integer)
+    }
+}
+
+/// F(n) and F(n - 1) by doubling.
+fn fibonacci_2(n: u32) -> (BigInt, BigInt) {
+    if n == 0 {
+        return (BigInt::zero(), BigInt::from(1));
+    }
+
+    // F(k) and F(k + 1) for the prefixes k of n
+    let (mut f_k, mut f_kp1) = (BigInt::zero(), BigInt::from(1));
+    for i in (0..32 - n.leading_zeros()).rev() {
+        // F(2k) = F(k) (2 F(k + 1) - F(k)), F(2k + 1) = F(k)^2 + F(k + 1)^2
+        let f_2k = &f_k * ((&f_kp1 << 1u32) - &f_k);
+        let f_2kp1 = &f_k * &f_k + &f_kp1 * &f_kp1;
+        if (n >> i) & 1 == 1 {
+            f_kp1 = &f_2k + &f_2kp1;
+            f_k = f_2kp1;
+        } else {
+            f_k = f_2k;
+            f_kp1 = f_2kp1;
+        }
+    }
+
+    let f_km1 = &f_kp1 - &f_k;
+    (f_k, f_km1)
+}
+
+impl Complete for Incomplete {
+    type Completed = Integer;
+
+    fn complete(self) -> Integer {
+        Integer(self.0)
+    }
+}
+
+impl Complete for IncompletePair {
+    type Completed = (Integer, Integer);
+
+    fn complete(self) -> (Integer, Integer) {
+        (Integer(self.0), Integer(self.1))
+    }
+}
+
+impl From<Incomplete> for Integer {
+    fn from(x: Incomplete) -> Self {
+        x.complete()
+    }
+}
+
+impl From<IncompletePair> for (Integer, Integer) {
+    fn from(x: IncompletePair) -> Self {
+        x.complete()
+    }
+}
+
+impl From<&Integer> for Integer {
+    fn from(x: &Integer) -> Self {
+        x.clone()
+    }
+}
+
+impl Assign for Integer {
+    fn assign(&mut self, src: Integer) {
+        *self = src;
+    }
+}
+
+impl Assign<&Integer> for Integer {
+    fn assign(&mut self, src: &Integer) {
+        self.0.clone_from(&src.0);
+    }
+}
+
+impl Assign<Incomplete> for Integer {
+    fn assign(&mut self, src: Incomplete) {
+        self.0 = src.0;
+    }
+}
+
+impl FromStr for Integer {
+    type Err = ParseBigIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Integer)
+    }
+}
+
+impl fmt::Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Debug for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl fmt::LowerHex for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl fmt::UpperHex for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Binary for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Binary::fmt(&self.0, f)
+    }
+}
+
+impl Neg for Integer {
+    type Output = Integer;
+
+    fn neg(self) -> Integer {
+        Integer(-self.0)
+    }
+}
+
+impl Neg for &Integer {
+    type Output = Incomplete;
+
+    fn neg(self) -> Incomplete {
+        Incomplete(-&self.0)
+    }
+}
+
+impl ops::Pow<u32> for Integer {
+    type Output = Integer;
+
+    fn pow(self, exp: u32) -> Integer {
+        Integer(num_traits::Pow::pow(self.0, exp))
+    }
+}
+
+impl ops::Pow<u32> for &Integer {
+    type Output = Incomplete;
+
+    fn pow(self, exp: u32) -> Incomplete {
+        Incomplete(num_traits::Pow::pow(&self.0, exp))
+    }
+}
+
+impl ops::RemRounding<&Integer> for Integer {
+    type Output = Integer;
+
+    fn rem_euc(self, rhs: &Integer) -> Integer {
+        Integer(self.0.mod_floor(&rhs.0.abs()))
+    }
+}
+
+impl ops::RemRounding for Integer {
+    type Output = Integer;
+
+    fn rem_euc(self, rhs: Integer) -> Integer {
+        self.rem_euc(&rhs)
+    }
+}
+
+impl Sum for Integer {
+    fn sum<I: Iterator<Item = Integer>>(iter: I) -> Integer {
+        Integer(iter.map(|x| x.0).sum())
+    }
+}
+
+impl<'a> Sum<&'a Integer> for Integer {
+    fn sum<I: Iterator<Item = &'a Integer>>(iter: I) -> Integer {
+        Integer(iter.map(|x| &x.0).sum())
+    }
+}
+
+/// Implements an arithmetic operator for every mix of owned, borrowed and
+/// incomplete operands that rug has.
+macro_rules! arith {
+    ($($Op:ident $op:ident $OpAssign:ident $op_assign:ident),*) => {$(
+        impl $Op for Integer {
+            type Output = Integer;
+
+            fn $op(self, rhs: Integer) -> Integer {
+                Integer(self.0.$op(rhs.0))
+            }
+        }
+
+        impl $Op<&Integer> for Integer {
+            type Output = Integer;
+
+            fn $op(self, rhs: &Integer) -> Integer {
+                Integer(self.0.$op(&rhs.0))
+            }
+        }
+
+        impl $Op<Incomplete> for Integer {
+            type Output = Integer;
+
+            fn $op(self, rhs: Incomplete) -> Integer {
+                Integer(self.0.$op(rhs.0))
+            }
+        }
+
+        impl $Op<Integer> for &Integer {
+            type Output = Integer;
+
+            fn $op(self, rhs: Integer) -> Integer {
+                Integer((&self.0).$op(rhs.0))
+            }
+        }
+
+        impl $Op<&Integer> for &Integer {
+            type Output = Incomplete;
+
+            fn $op(self, rhs: &Integer) -> Incomplete {
+                Incomplete((&self.0).$op(&rhs.0))
+            }
+        }
+
+        impl $Op<Incomplete> for &Integer {
+            type Output = Incomplete;
+
+            fn $op(self, rhs: Incomplete) -> Incomplete {
+                Incomplete((&self.0).$op(rhs.0))
+            }
+        }
+
+        impl $Op<Integer> for Incomplete {
+            type Output = Integer;
+
+            fn $op(self, rhs: Integer) -> Integer {
+                Integer(self.0.$op(rhs.0))
+            }
+        }
+
+        impl $OpAssign for Integer {
+            fn $op_assign(&mut self, rhs: Integer) {
+                self.0.$op_assign(rhs.0);
+            }
+        }
+
+        impl $OpAssign<&Integer> for Integer {
+            fn $op_assign(&mut self, rhs: &Integer) {
+                self.0.$op_assign(&rhs.0);
+            }
+        }
+
+        impl $OpAssign<Incomplete> for Integer {
+            fn $op_assign(&mut self, rhs: Incomplete) {
+                self.0.$op_assign(rhs.0);
+            }
+        }
+    )*};
+}
+
+arith! {
+    Add add AddAssign add_assign,
+    Sub sub SubAssign sub_assign,
+    Mul mul MulAssign mul_assign,
+    Div div DivAssign div_assign,
+    Rem rem RemAssign rem_assign
+}
+
+/// Implements conversions, comparisons and arithmetic with primitive integers.
+macro_rules! primitive {
+    ($($t:ty),*) => {$(
+        impl From<$t> for Integer {
+            fn from(x: $t) -> Self {
+                Integer(BigInt::from(x))
+            }
+        }
+
+        impl Assign<$t> for Integer {
+            fn assign(&mut self, src: $t) {
+                self.0 = BigInt::from(src);
+            }
+        }
+
+        impl PartialEq<$t> for Integer {
+            fn eq(&self, other: &$t) -> bool {
+                self.0 == BigInt::from(*other)
+            }
+        }
+
+        impl PartialEq<Integer> for $t {
+            fn eq(&self, other: &Integer) -> bool {
+                BigInt::from(*self) == other.0
+            }
+        }
+
+        impl PartialOrd<$t> for Integer {
+            fn partial_cmp(&self, other: &$t) -> Option<Ordering> {
+                self.0.partial_cmp(&BigInt::from(*other))
+            }
+        }
+
+        impl PartialOrd<Integer> for $t {
+            fn partial_cmp(&self, other: &Integer) -> Option<Ordering> {
+                BigInt::from(*self).partial_cmp(&other.0)
+            }
+        }
+
+        primitive_arith!($t, Add add AddAssign add_assign);
+        primitive_arith!($t, Sub sub SubAssign sub_assign);
+        primitive_arith!($t, Mul mul MulAssign mul_assign);
+        primitive_arith!($t, Div div DivAssign div_assign);
+        primitive_arith!($t, Rem rem RemAssign rem_assign);
+    )*};
+}
+
+/// Implements an arithmetic operator between `Integer` and a primitive, on
+/// either side.
+macro_rules! primitive_arith {
+    ($t:ty, $Op:ident $op:ident $OpAssign:ident $op_assign:ident) => {
+        impl $Op<$t> for Integer {
+            type Output = Integer;
+
+            fn $op(self, rhs: $t) -> Integer {
+                Integer(self.0.$op(BigInt::from(rhs)))
+            }
+        }
+
+        impl $Op<$t> for &Integer {
+            type Output = Incomplete;
+
+            fn $op(self, rhs: $t) -> Incomplete {
+                Incomplete((&self.0).$op(BigInt::from(rhs)))
+            }
+        }
+
+        impl $Op<Integer> for $t {
+            type Output = Integer;
+
+            fn $op(self, rhs: Integer) -> Integer {
+                Integer(BigInt::from(self).$op(rhs.0))
+            }
+        }
+
+        impl $Op<&Integer> for $t {
+            type Output = Incomplete;
+
+            fn $op(self, rhs: &Integer) -> Incomplete {
+                Incomplete(BigInt::from(self).$op(&rhs.0))
+            }
+        }
+
+        impl $OpAssign<$t> for Integer {
+            fn $op_assign(&mut self, rhs: $t) {
+                self.0.$op_assign(BigInt::from(rhs));
+            }
+        }
+    };
+}
+
+primitive!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Shifts x left by bits, or right if bits is negative. Shifting right rounds
+/// down, like GMP.
+fn shift_left(x: &BigInt, bits: i64) -> BigInt {
+    if bits >= 0 {
+        x << bits.unsigned_abs()
+    } else {
+        x >> bits.unsigned_abs()
+    }
+}
+
+/// Implements shifts by a bit count. Like rug, a negative count shifts the
+/// other way.
+macro_rules! shift {
+    ($($t:ty),*) => {$(
+        impl Shl<$t> for Integer {
+            type Output = Integer;
+
+            fn shl(self, bits: $t) -> Integer {
+                Integer(shift_left(&self.0, bits as i64))
+            }
+        }
+
+        impl Shl<$t> for &Integer {
+            type Output = Incomplete;
+
+            fn shl(self, bits: $t) -> Incomplete {
+                Incomplete(shift_left(&self.0, bits as i64))
+            }
+        }
+
+        impl Shr<$t> for Integer {
+            type Output = Integer;
+
+            fn shr(self, bits: $t) -> Integer {
+                Integer(shift_left(&self.0, -(bits as i64)))
+            }
+        }
+
+        impl Shr<$t> for &Integer {
+            type Output = Incomplete;
+
+            fn shr(self, bits: $t) -> Incomplete {
+                Incomplete(shift_left(&self.0, -(bits as i64)))
+            }
+        }
+
+        impl ShlAssign<$t> for Integer {
+            fn shl_assign(&mut self, bits: $t) {
+                self.0 = shift_left(&self.0, bits as i64);
+            }
+        }
+
+        impl ShrAssign<$t> for Integer {
+            fn shr_assign(&mut self, bits: $t) {
+                self.0 = shift_left(&self.0, -(bits as i64));
+            }
+        }
+    )*};
+}
+
+shift!(i32, u32, usize);
+
+#[cfg(test)]
+mod tests {
+    use super::{ops::RemRounding, *};
+
+    #[test]
+    fn test_fibonacci() {
+        let first = [0u32, 1, 1, 2, 3, 5, 8, 13, 21, 34, 55];
+        for (n, f_n) in first.iter().enumerate().skip(1) {
+            let (f, f_prev) = Integer::fibonacci_2(n as u32).complete();
+            assert_eq!(f, *f_n);
+            assert_eq!(f_prev, first[n - 1]);
+        }
+        assert_eq!(
+            Integer::fibonacci_2(0).complete(),
+            (Integer::from(0), Integer::from(1))
+        );
+        assert_eq!(Integer::from(Integer::lucas(10)), 123);
+    }
+
+    #[test]
+    fn test_rounding() {
+        let m = Integer::from(7);
+        assert_eq!(Integer::from(-9).rem_euc(&m), 5);
+        assert_eq!(Integer::from(-9) % &m, -2);
+        // shifts round down, like GMP's
+        assert_eq!(Integer::from(-9) >> 1u32, -5);
+        assert_eq!(Integer::from(-9) >> 1, -5);
+        assert_eq!(Integer::from(3) >> -2, 12);
+    }
+
+    #[test]
+    fn test_pow_mod() {
+        let m = Integer::from(7);
+        assert_eq!(Integer::from(3).pow_mod(&6.into(), &m), Ok(1.into()));
+        assert_eq!(Integer::from(-3).pow_mod(&3.into(), &m), Ok(1.into()));
+        assert_eq!(Integer::from(3).pow_mod(&(-1).into(), &m), Ok(5.into()));
+        assert_eq!(Integer::from(14).pow_mod(&(-1).into(), &m), Err(14.into()));
+    }
+}
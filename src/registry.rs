@@ -0,0 +1,202 @@
+//! A list of every algorithm in the crate with what you need to know to pick
+//! one at runtime: how it scales, how big an index it can handle, whether it's
+//! exact, and whether it recurses.
+
+use crate::{
+    error::MAX_FIB_INDEX, fib_finder::FibFinder, Auto, BinetZ5, Cassini, CassiniGMP, DPIterator,
+    MatExponentiator, MemoizedRecursor, NaiveRecursor, ParallelCassini,
+};
+#[cfg(feature = "rug")]
+use crate::{Binet, GMP};
+
+/// How the number of big integer operations an algorithm does grows with n.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Complexity {
+    /// O(phi^n), like the naïve recursion.
+    Exponential,
+    /// O(n).
+    Linear,
+    /// O(log n), like anything that doubles.
+    Logarithmic,
+}
+
+/// An algorithm and what we know about it.
+#[derive(Copy, Clone, Debug)]
+pub struct AlgorithmInfo {
+    /// The name of the struct that implements it.
+    pub name: &'static str,
+    /// A longer human-readable name.
+    pub description: &'static str,
+    /// How its running time grows.
+    pub complexity: Complexity,
+    /// The biggest index it can handle without taking forever, overflowing
+    /// the stack or losing precision. Its `try_fib` refuses anything bigger.
+    pub max_safe_n: u64,
+    /// Whether it always gives the exact answer.
+    pub exact: bool,
+    /// Whether it recurses once per index, and so can overflow the stack.
+    pub recursive: bool,
+    make: fn() -> Box<dyn FibFinder>,
+}
+
+impl AlgorithmInfo {
+    /// Makes a fresh instance of the algorithm.
+    pub fn make(&self) -> Box<dyn FibFinder> {
+        (self.make)()
+    }
+}
+
+/// Every algorithm, from slowest to fastest, and then `Auto`, which picks
+/// between them.
+pub fn algorithms() -> Vec<AlgorithmInfo> {
+    let mut algs = vec![
+        AlgorithmInfo {
+            name: "NaiveRecursor",
+            description: "Naïve Recursive",
+            complexity: Complexity::Exponential,
+            max_safe_n: NaiveRecursor::MAX_INDEX,
+            exact: true,
+            recursive: true,
+            make: || Box::new(NaiveRecursor::default()),
+        },
+        AlgorithmInfo {
+            name: "MemoizedRecursor",
+            description: "Memoized Recursive",
+            complexity: Complexity::Linear,
+            max_safe_n: MemoizedRecursor::MAX_INDEX,
+            exact: true,
+            recursive: true,
+            make: || Box::new(MemoizedRecursor::default()),
+        },
+        AlgorithmInfo {
+            name: "DPIterator",
+            description: "DP",
+            complexity: Complexity::Linear,
+            max_safe_n: DPIterator::MAX_INDEX,
+            exact: true,
+            recursive: false,
+            make: || Box::new(DPIterator::default()),
+        },
+        AlgorithmInfo {
+            name: "MatExponentiator",
+            description: "Matrix Exponentiation",
+            complexity: Complexity::Logarithmic,
+            max_safe_n: MatExponentiator::MAX_INDEX,
+            exact: true,
+            recursive: false,
+            make: || Box::new(MatExponentiator::default()),
+        },
+    ];
+    // Binet's formula needs MPFR's floats
+    #[cfg(feature = "rug")]
+    algs.push(AlgorithmInfo {
+        name: "Binet",
+        description: "Binet",
+        complexity: Complexity::Logarithmic,
+        max_safe_n: Binet::MAX_INDEX,
+        exact: false,
+        recursive: false,
+        make: || Box::new(Binet::default()),
+    });
+    algs.extend([
+        AlgorithmInfo {
+            name: "BinetZ5",
+            description: "Binet (Z5)",
+            complexity: Complexity::Logarithmic,
+            max_safe_n: MAX_FIB_INDEX,
+            exact: true,
+            recursive: false,
+            make: || Box::new(BinetZ5::default()),
+        },
+        AlgorithmInfo {
+            name: "Cassini",
+            description: "Efficient Matrix Exponentiation",
+            complexity: Complexity::Logarithmic,
+            max_safe_n: MAX_FIB_INDEX,
+            exact: true,
+            recursive: false,
+            make: || Box::new(Cassini::default()),
+        },
+        AlgorithmInfo {
+            name: "CassiniGMP",
+            description: "GMP Algorithm Port",
+            complexity: Complexity::Logarithmic,
+            max_safe_n: MAX_FIB_INDEX,
+            exact: true,
+            recursive: false,
+            make: || Box::new(CassiniGMP::default()),
+        },
+        AlgorithmInfo {
+            name: "ParallelCassini",
+            description: "Parallel GMP Algorithm Port",
+            complexity: Complexity::Logarithmic,
+            max_safe_n: MAX_FIB_INDEX,
+            exact: true,
+            recursive: false,
+            make: || Box::new(ParallelCassini::default()),
+        },
+    ]);
+    // without rug there's no GMP behind this, just the same doubling as
+    // CassiniGMP, so it isn't worth listing
+    #[cfg(feature = "rug")]
+    algs.push(AlgorithmInfo {
+        name: "GMP",
+        description: "GMP",
+        complexity: Complexity::Logarithmic,
+        max_safe_n: MAX_FIB_INDEX,
+        exact: true,
+        recursive: false,
+        make: || Box::new(GMP::default()),
+    });
+    algs.push(AlgorithmInfo {
+        name: "Auto",
+        description: "Automatic",
+        complexity: Complexity::Logarithmic,
+        max_safe_n: MAX_FIB_INDEX,
+        exact: true,
+        recursive: false,
+        make: || Box::new(Auto::default()),
+    });
+    algs
+}
+
+/// Looks up an algorithm by the name of its struct, ignoring case.
+pub fn find_algorithm(name: &str) -> Option<AlgorithmInfo> {
+    algorithms()
+        .into_iter()
+        .find(|alg| alg.name.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_algorithms_agree() {
+        let mut reference = Cassini::default();
+        for alg in algorithms() {
+            let mut finder = alg.make();
+            for n in 0..=alg.max_safe_n.min(30) {
+                assert_eq!(finder.fib(n), reference.fib(n), "{} at {}", alg.name, n);
+            }
+            // the naïve recursion takes seconds at its limit
+            if alg.max_safe_n <= 1000 && alg.complexity > Complexity::Exponential {
+                assert!(finder.try_fib(alg.max_safe_n).is_ok());
+            }
+            if alg.max_safe_n < MAX_FIB_INDEX {
+                assert!(finder.try_fib(alg.max_safe_n + 1).is_err(), "{}", alg.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_algorithm() {
+        for alg in algorithms() {
+            assert_eq!(find_algorithm(alg.name).unwrap().name, alg.name);
+        }
+        assert_eq!(find_algorithm("cassinigmp").unwrap().name, "CassiniGMP");
+        assert!(find_algorithm("Fibonacci").is_none());
+        #[cfg(not(feature = "rug"))]
+        assert!(find_algorithm("GMP").is_none());
+    }
+}
@@ -2,6 +2,8 @@
 
 use std::ops::MulAssign;
 
+use crate::bigint::Integer;
+
 /// Raises base to power exp. ident is x^0 for any x and the identity element
 /// under the group operation.
 pub fn power<T: Clone + MulAssign>(base: T, exp: u64, ident: T) -> T {
@@ -18,7 +20,23 @@ pub fn power<T: Clone + MulAssign>(base: T, exp: u64, ident: T) -> T {
         p *= p.clone();
     }
 
-    return prod;
+    prod
+}
+
+/// Raises base to power exp, like `power`, but for exponents too big to fit in
+/// a machine word. exp must not be negative.
+pub fn power_integer<T: Clone + MulAssign>(base: T, exp: &Integer, ident: T) -> T {
+    assert!(*exp >= 0, "exponent must not be negative");
+    let mut p = base;
+    let mut prod = ident;
+    for i in 0..exp.significant_bits() {
+        if exp.get_bit(i) {
+            prod *= p.clone();
+        }
+        p *= p.clone();
+    }
+
+    prod
 }
 
 #[cfg(test)]
@@ -33,4 +51,20 @@ mod tests {
             assert_eq!(power(base, exp.into(), 1), base.pow(exp))
         }
     }
+
+    #[test]
+    fn test_int_pow_integer() {
+        let bases: Vec<u64> = vec![3, 4, 2, 5, 10, 6];
+        let exps: Vec<u32> = vec![8, 10, 17, 5, 1, 0];
+        for (base, exp) in bases.into_iter().zip(exps) {
+            assert_eq!(power_integer(base, &Integer::from(exp), 1), base.pow(exp))
+        }
+
+        let big_exp = Integer::from(Integer::u_pow_u(2, 70)) + 3;
+        assert_eq!(power_integer(1u64, &big_exp, 1), 1);
+        assert_eq!(
+            power_integer(Integer::from(2), &Integer::from(100), Integer::from(1)),
+            Integer::from(Integer::u_pow_u(2, 100))
+        );
+    }
 }
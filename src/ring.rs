@@ -0,0 +1,247 @@
+//! The arithmetic the Fibonacci recurrences actually need, so the same
+//! algorithm can run over more than just big integers. The recurrences only
+//! ever add, subtract, multiply and double, so they hold in any ring: run over
+//! the integers mod m they give F(n) mod m, and over `Wrapping<u128>` they give
+//! F(n) mod 2^128, which is exact as long as F(n) fits. `Zmod`, `Gf`, `Zsqrt5`
+//! and `Mat2x2` give modular arithmetic, finite fields and matrices over them.
+//! `repeated_squaring::power` already works this way for anything it can
+//! multiply.
+
+use std::{
+    num::Wrapping,
+    ops::{Add, Mul, Sub},
+};
+
+use crate::bigint::{Complete, Integer};
+
+/// A ring with a one. It doesn't have to be commutative, like the ring of 2x2
+/// matrices: every value the recurrences make is F(n) times one, and those
+/// always commute. The operators work on owned values, with the right-hand side
+/// either owned or borrowed, which is what rug's `Integer` supports without
+/// copying.
+pub trait Ring:
+    Clone
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + for<'a> Add<&'a Self, Output = Self>
+    + for<'a> Sub<&'a Self, Output = Self>
+    + for<'a> Mul<&'a Self, Output = Self>
+{
+    /// The element you get by adding up x ones.
+    fn from_small(x: u32) -> Self;
+
+    /// The additive identity.
+    fn zero() -> Self {
+        Self::from_small(0)
+    }
+
+    /// The multiplicative identity.
+    fn one() -> Self {
+        Self::from_small(1)
+    }
+
+    /// self times rhs, without giving up either. The default copies self,
+    /// which types that can multiply two borrowed values should avoid.
+    fn mul_ref(&self, rhs: &Self) -> Self {
+        self.clone() * rhs
+    }
+
+    /// self times itself.
+    fn square(&self) -> Self {
+        self.mul_ref(self)
+    }
+
+    /// self times 2^bits, which for binary integers is a left shift.
+    fn mul_2exp(self, bits: u32) -> Self {
+        (0..bits).fold(self, |x, _| x.clone() + &x)
+    }
+
+    /// self raised to the power exp, by repeated squaring.
+    fn pow(&self, exp: u64) -> Self {
+        let mut prod = Self::one();
+        for i in (0..64 - exp.leading_zeros()).rev() {
+            prod = prod.square();
+            if exp >> i & 1 == 1 {
+                prod = prod * self;
+            }
+        }
+        prod
+    }
+}
+
+impl Ring for Integer {
+    fn from_small(x: u32) -> Self {
+        Integer::from(x)
+    }
+
+    fn mul_ref(&self, rhs: &Self) -> Self {
+        (self * rhs).complete()
+    }
+
+    fn square(&self) -> Self {
+        self.square_ref().complete()
+    }
+
+    fn mul_2exp(self, bits: u32) -> Self {
+        self << bits
+    }
+}
+
+/// Forwards the owned-operand operators of a generic type to the borrowed ones,
+/// as in `by_value!(impl<T: Ring> Zsqrt5<T>; Add add, Mul mul)`.
+macro_rules! by_value {
+    (impl<$g:ident: $bound:path> $ty:ty; $($tr:ident $f:ident),*) => {$(
+        impl<$g: $bound> $tr for $ty {
+            type Output = Self;
+
+            fn $f(self, rhs: Self) -> Self {
+                self.$f(&rhs)
+            }
+        }
+    )*};
+}
+
+pub(crate) use by_value;
+
+/// Implements `Ring` for machine integers with wrapping arithmetic, which is
+/// arithmetic mod 2^BITS.
+macro_rules! wrapping_ring {
+    ($($t:ty),*) => {$(
+        impl Ring for Wrapping<$t> {
+            fn from_small(x: u32) -> Self {
+                Wrapping(x.into())
+            }
+
+            fn mul_2exp(self, bits: u32) -> Self {
+                Wrapping(self.0.checked_shl(bits).unwrap_or(0))
+            }
+        }
+    )*};
+}
+
+wrapping_ring!(u32, u64, u128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cassini, FibFinder, MatExponentiator, FIB_U128, MAX_U128_INDEX};
+
+    /// The algorithms with a `fib_ring`, so one test can cover them all.
+    trait FibRing: FibFinder {
+        fn fib_ring<T: Ring>(&mut self, n: u64) -> T;
+    }
+
+    impl FibRing for Cassini {
+        fn fib_ring<T: Ring>(&mut self, n: u64) -> T {
+            Cassini::fib_ring(self, n)
+        }
+    }
+
+    impl FibRing for MatExponentiator {
+        fn fib_ring<T: Ring>(&mut self, n: u64) -> T {
+            MatExponentiator::fib_ring(self, n)
+        }
+    }
+
+    fn check_fib_ring(mut alg: impl FibRing) {
+        for n in 0..=MAX_U128_INDEX {
+            assert_eq!(alg.fib_ring::<Wrapping<u128>>(n).0, FIB_U128[n as usize]);
+            assert_eq!(alg.fib_ring::<Integer>(n), alg.fib(n));
+        }
+
+        // past what fits, we get the answer mod 2^64
+        let two_64 = Integer::from(Integer::u_pow_u(2, 64));
+        for n in [94, 100, 1000, 10000] {
+            assert_eq!(alg.fib_ring::<Wrapping<u64>>(n).0, alg.fib(n) % &two_64);
+        }
+    }
+
+    #[test]
+    fn test_fib_ring() {
+        check_fib_ring(Cassini::default());
+        check_fib_ring(MatExponentiator::default());
+    }
+
+    /// Checks the default methods against the ones `Integer` overrides.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Plain(Integer);
+
+    impl Add for Plain {
+        type Output = Plain;
+
+        fn add(self, rhs: Plain) -> Plain {
+            Plain(self.0 + rhs.0)
+        }
+    }
+
+    impl Add<&Plain> for Plain {
+        type Output = Plain;
+
+        fn add(self, rhs: &Plain) -> Plain {
+            Plain(self.0 + &rhs.0)
+        }
+    }
+
+    impl Sub for Plain {
+        type Output = Plain;
+
+        fn sub(self, rhs: Plain) -> Plain {
+            Plain(self.0 - rhs.0)
+        }
+    }
+
+    impl Sub<&Plain> for Plain {
+        type Output = Plain;
+
+        fn sub(self, rhs: &Plain) -> Plain {
+            Plain(self.0 - &rhs.0)
+        }
+    }
+
+    impl Mul for Plain {
+        type Output = Plain;
+
+        fn mul(self, rhs: Plain) -> Plain {
+            Plain(self.0 * rhs.0)
+        }
+    }
+
+    impl Mul<&Plain> for Plain {
+        type Output = Plain;
+
+        fn mul(self, rhs: &Plain) -> Plain {
+            Plain(self.0 * &rhs.0)
+        }
+    }
+
+    impl Ring for Plain {
+        fn from_small(x: u32) -> Self {
+            Plain(x.into())
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        for x in [0, 1, 7, -12345] {
+            let x = Integer::from(x);
+            assert_eq!(Plain(x.clone()).square().0, Ring::square(&x));
+            let y = Integer::from(-99);
+            assert_eq!(Plain(x.clone()).mul_ref(&Plain(y.clone())).0, x.mul_ref(&y));
+            for bits in [0, 1, 5, 70] {
+                assert_eq!(Plain(x.clone()).mul_2exp(bits).0, x.clone().mul_2exp(bits));
+            }
+        }
+        assert_eq!(Plain::zero(), Plain(0.into()));
+        assert_eq!(Plain::one(), Plain(1.into()));
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let x = Wrapping(u64::MAX - 1);
+        assert_eq!(x.mul_2exp(1), Wrapping(u64::MAX - 3));
+        assert_eq!(x.mul_2exp(64), Wrapping(0));
+        assert_eq!(x.square(), Wrapping(4));
+        assert_eq!(Wrapping::<u128>::from_small(12), Wrapping(12));
+    }
+}
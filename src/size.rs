@@ -0,0 +1,109 @@
+//! How big F(n) is, without finding it. F(n) is within a hair of
+//! phi^n / sqrt 5, so in base b it has floor(n log_b(phi) - log_b(sqrt 5)) + 1
+//! digits. A float gets that logarithm almost right, but almost isn't enough
+//! when it lands near an integer, so we bound it from both sides with directed
+//! rounding and add precision until both bounds have the same floor.
+
+use rug::{float::Round, Float};
+
+use crate::small::{fib_u128, MAX_U128_INDEX};
+
+/// The number of digits F(n) has in the given base, which must be at least 2.
+/// F(0) = 0 has one digit, like `to_string` gives.
+pub fn fib_num_digits(n: u64, base: u32) -> u64 {
+    assert!(base >= 2, "base must be at least 2, not {}", base);
+    match fib_u128(n) {
+        // small enough that psi^n still matters, but then F(n) is right there
+        Some(mut f_n) => {
+            let mut digits = 1;
+            while f_n >= u128::from(base) {
+                f_n /= u128::from(base);
+                digits += 1;
+            }
+            digits
+        }
+        None => floor_log(n, base) + 1,
+    }
+}
+
+/// The number of bits in F(n), like `Integer::significant_bits`, so 0 for
+/// F(0).
+pub fn fib_bit_length(n: u64) -> u64 {
+    match n {
+        0 => 0,
+        _ => fib_num_digits(n, 2),
+    }
+}
+
+/// floor(n log_b(phi) - log_b(sqrt 5)), for n past `MAX_U128_INDEX`. The only
+/// perfect powers in the Fibonacci sequence are 0, 1, 8 and 144, so phi^n /
+/// sqrt 5 is never within psi^n of a power of b and the logarithm is never
+/// exactly an integer: with enough precision the bounds always agree.
+fn floor_log(n: u64, base: u32) -> u64 {
+    debug_assert!(n > MAX_U128_INDEX);
+    let mut prec = 128;
+    loop {
+        let lo = log_bound(n, base, prec, Round::Down, Round::Up).floor();
+        let hi = log_bound(n, base, prec, Round::Up, Round::Down).floor();
+        if lo == hi {
+            return lo.to_integer().unwrap().to_u64().unwrap();
+        }
+        prec *= 2;
+    }
+}
+
+/// A bound on (n ln(phi) - ln(5) / 2) / ln(b), below it if toward is
+/// `Round::Down` and above it if toward is `Round::Up`. away is the other
+/// direction: the terms that get subtracted or divided by are rounded that
+/// way, so every step pushes the result the same way.
+fn log_bound(n: u64, base: u32, prec: u32, toward: Round, away: Round) -> Float {
+    let mut sqrt5 = Float::with_val(prec, 5);
+    sqrt5.sqrt_round(toward);
+    let mut ln_phi = Float::with_val_round(prec, &sqrt5 + 1u32, toward).0;
+    ln_phi = Float::with_val_round(prec, &ln_phi / 2u32, toward).0;
+    ln_phi.ln_round(toward);
+
+    let mut ln_sqrt5 = Float::with_val(prec, 5);
+    ln_sqrt5.ln_round(away);
+    ln_sqrt5 = Float::with_val_round(prec, &ln_sqrt5 / 2u32, away).0;
+
+    let mut ln_base = Float::with_val(prec, base);
+    ln_base.ln_round(away);
+
+    // n > 186, so the numerator is well above 0 and dividing by a bigger ln(b)
+    // makes it smaller
+    let ln_f_n = Float::with_val_round(prec, &ln_phi * n, toward).0;
+    let ln_f_n = Float::with_val_round(prec, &ln_f_n - &ln_sqrt5, toward).0;
+    Float::with_val_round(prec, &ln_f_n / &ln_base, toward).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cassini, FibFinder};
+
+    #[test]
+    fn test_fib_num_digits() {
+        let mut alg = Cassini::default();
+        for n in (0..300).chain([1000, 1001, 4782, 4783, 10000, 123_456]) {
+            let f_n = alg.fib(n);
+            for base in [2, 3, 10, 16, 36] {
+                let digits = f_n.to_string_radix(base as i32).len() as u64;
+                assert_eq!(fib_num_digits(n, base), digits);
+            }
+            assert_eq!(fib_bit_length(n), u64::from(f_n.significant_bits()));
+        }
+
+        // F(4782) is the first with 1000 digits
+        assert_eq!(fib_num_digits(4781, 10), 999);
+        assert_eq!(fib_num_digits(4782, 10), 1000);
+
+        // checked against Python's decimal module with 200 digits
+        assert_eq!(fib_num_digits(1_000_000_000_000, 10), 208_987_640_250);
+        assert_eq!(
+            fib_num_digits(1_000_000_000_000_000_000, 10),
+            208_987_640_249_978_734
+        );
+        assert_eq!(fib_num_digits(u64::MAX, 10), 3_855_141_514_259_838_963);
+    }
+}
@@ -0,0 +1,107 @@
+//! Fibonacci numbers small enough to fit in a machine word, looked up in tables
+//! built at compile time without touching the heap. For small n, allocating the
+//! `Integer` costs more than finding the answer, so this is the fast path when
+//! the answer is known to fit. Everything here is const, so it works in array
+//! lengths and other constants too.
+
+/// The largest n for which F(n) fits in a u64.
+pub const MAX_U64_INDEX: u64 = 93;
+
+/// The largest n for which F(n) fits in a u128.
+pub const MAX_U128_INDEX: u64 = 186;
+
+/// F(0) through F(93), every Fibonacci number that fits in a u64.
+pub const FIB_U64: [u64; MAX_U64_INDEX as usize + 1] = {
+    // the start of the u128 table, narrowed
+    let mut table = [0; MAX_U64_INDEX as usize + 1];
+    let mut i = 0;
+    while i < table.len() {
+        table[i] = FIB_U128[i] as u64;
+        i += 1;
+    }
+    table
+};
+
+/// F(0) through F(186), every Fibonacci number that fits in a u128.
+pub const FIB_U128: [u128; MAX_U128_INDEX as usize + 1] = {
+    let mut table = [0; MAX_U128_INDEX as usize + 1];
+    table[1] = 1;
+    let mut i = 2;
+    while i < table.len() {
+        table[i] = table[i - 1] + table[i - 2];
+        i += 1;
+    }
+    table
+};
+
+/// Finds F(n) if it fits in a u64, which it does up to n = 93.
+pub const fn fib_u64(n: u64) -> Option<u64> {
+    if n > MAX_U64_INDEX {
+        None
+    } else {
+        Some(FIB_U64[n as usize])
+    }
+}
+
+/// Finds F(n) if it fits in a u128, which it does up to n = 186.
+pub const fn fib_u128(n: u64) -> Option<u128> {
+    if n > MAX_U128_INDEX {
+        None
+    } else {
+        Some(FIB_U128[n as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DPIterator, FibFinder};
+
+    #[test]
+    fn test_fib_u128() {
+        let mut reference = DPIterator::default();
+        for n in 0..=MAX_U128_INDEX {
+            assert_eq!(fib_u128(n).unwrap(), reference.fib(n));
+        }
+
+        assert_eq!(
+            fib_u128(186),
+            Some(332_825_110_087_067_562_321_196_029_789_634_457_848)
+        );
+        assert!(reference.fib(187) > u128::MAX);
+        assert_eq!(fib_u128(187), None);
+        assert_eq!(fib_u128(u64::MAX), None);
+    }
+
+    #[test]
+    fn test_overflow() {
+        // the tables stop right where the next number would overflow
+        let last = MAX_U64_INDEX as usize;
+        assert_eq!(FIB_U64[last - 1].checked_add(FIB_U64[last]), None);
+        let last = MAX_U128_INDEX as usize;
+        assert_eq!(FIB_U128[last - 1].checked_add(FIB_U128[last]), None);
+    }
+
+    #[test]
+    fn test_const() {
+        const F_10: usize = FIB_U64[10] as usize;
+        const F_50: Option<u64> = fib_u64(50);
+        const F_200: Option<u128> = fib_u128(200);
+        let buckets = [0u8; F_10];
+        assert_eq!(buckets.len(), 55);
+        assert_eq!(F_50, Some(12_586_269_025));
+        assert_eq!(F_200, None);
+    }
+
+    #[test]
+    fn test_fib_u64() {
+        let mut reference = DPIterator::default();
+        for n in 0..=MAX_U64_INDEX {
+            assert_eq!(fib_u64(n).unwrap(), reference.fib(n));
+        }
+
+        assert_eq!(fib_u64(93), Some(12_200_160_415_121_876_738));
+        assert!(reference.fib(94) > u64::MAX);
+        assert_eq!(fib_u64(94), None);
+    }
+}
@@ -0,0 +1,243 @@
+//! The integers mod a fixed machine-word modulus, as a `Ring`. For odd moduli
+//! values are kept in Montgomery form, x 2^64 mod M, which turns the division
+//! in every modular multiplication into a couple of multiplications and a
+//! shift. Even moduli, where 2^64 has no inverse, fall back to plain
+//! remainders.
+
+use std::{
+    fmt::{Debug, Display},
+    ops::{Add, Mul, Sub},
+};
+
+use crate::ring::Ring;
+
+/// An integer mod M, for any M from 1 to `u64::MAX`.
+#[derive(Copy, Clone, Hash, Eq, PartialEq)]
+pub struct Zmod<const M: u64> {
+    /// x 2^64 mod M if M is odd, otherwise just x mod M.
+    repr: u64,
+}
+
+impl<const M: u64> Zmod<M> {
+    /// Checked whenever a value gets made, so a modulus of 0 doesn't compile.
+    const VALID: () = assert!(M != 0, "the modulus must not be 0");
+
+    /// Whether values are stored in Montgomery form.
+    const MONTGOMERY: bool = M % 2 == 1;
+
+    /// -1/M mod 2^64. Each Newton step doubles the number of correct bits, and
+    /// M is its own inverse mod 8, so five steps get from 3 bits to 64.
+    const NEG_INV: u64 = {
+        let mut inv = M;
+        let mut i = 0;
+        while i < 5 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(M.wrapping_mul(inv)));
+            i += 1;
+        }
+        inv.wrapping_neg()
+    };
+
+    /// 2^128 mod M, which takes a value into Montgomery form in one reduction.
+    const R2: u64 = {
+        let r = (1u128 << 64) % M as u128;
+        (r * r % M as u128) as u64
+    };
+
+    /// x mod M.
+    pub fn new(x: u64) -> Self {
+        let () = Self::VALID;
+        if Self::MONTGOMERY {
+            Zmod {
+                repr: Self::redc((x % M) as u128 * Self::R2 as u128),
+            }
+        } else {
+            Zmod { repr: x % M }
+        }
+    }
+
+    /// The value as an integer from 0 to M - 1.
+    pub fn value(&self) -> u64 {
+        if Self::MONTGOMERY {
+            Self::redc(self.repr as u128)
+        } else {
+            self.repr
+        }
+    }
+
+    /// The multiplicative inverse, if there is one: that is, if the value and M
+    /// are coprime.
+    pub fn inverse(&self) -> Option<Self> {
+        // extended Euclid, keeping track of the coefficient of the value
+        let (mut r0, mut r1) = (M as i128, self.value() as i128);
+        let (mut t0, mut t1) = (0i128, 1i128);
+        while r1 != 0 {
+            let q = r0 / r1;
+            (r0, r1) = (r1, r0 - q * r1);
+            (t0, t1) = (t1, t0 - q * t1);
+        }
+
+        if r0 == 1 {
+            Some(Zmod::new(t0.rem_euclid(M as i128) as u64))
+        } else {
+            None
+        }
+    }
+
+    /// Montgomery reduction: t / 2^64 mod M, for any t below M 2^64.
+    fn redc(t: u128) -> u64 {
+        // adding a multiple of M that makes the bottom half 0, so the division
+        // is a shift. The sum can need 129 bits, so the carry goes back in.
+        let m = (t as u64).wrapping_mul(Self::NEG_INV);
+        let (sum, carry) = t.overflowing_add(m as u128 * M as u128);
+        let u = (sum >> 64) | ((carry as u128) << 64);
+        if u >= M as u128 {
+            (u - M as u128) as u64
+        } else {
+            u as u64
+        }
+    }
+}
+
+impl<const M: u64> Add for Zmod<M> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        // the representations add like the values do in both forms
+        let sum = self.repr as u128 + rhs.repr as u128;
+        Zmod {
+            repr: if sum >= M as u128 {
+                (sum - M as u128) as u64
+            } else {
+                sum as u64
+            },
+        }
+    }
+}
+
+impl<const M: u64> Sub for Zmod<M> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Zmod {
+            repr: if self.repr >= rhs.repr {
+                self.repr - rhs.repr
+            } else {
+                M - (rhs.repr - self.repr)
+            },
+        }
+    }
+}
+
+impl<const M: u64> Mul for Zmod<M> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let prod = self.repr as u128 * rhs.repr as u128;
+        Zmod {
+            repr: if Self::MONTGOMERY {
+                // (x R)(y R) / R = xy R
+                Self::redc(prod)
+            } else {
+                (prod % M as u128) as u64
+            },
+        }
+    }
+}
+
+/// Forwards the borrowed-operand operators to the owned ones, which is free
+/// for a `Copy` type.
+macro_rules! by_ref {
+    ($($tr:ident $f:ident),*) => {$(
+        impl<const M: u64> $tr<&Zmod<M>> for Zmod<M> {
+            type Output = Self;
+
+            fn $f(self, rhs: &Self) -> Self {
+                self.$f(*rhs)
+            }
+        }
+    )*};
+}
+
+by_ref!(Add add, Sub sub, Mul mul);
+
+impl<const M: u64> Ring for Zmod<M> {
+    fn from_small(x: u32) -> Self {
+        Zmod::new(x.into())
+    }
+}
+
+impl<const M: u64> Display for Zmod<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} mod {}", self.value(), M)
+    }
+}
+
+impl<const M: u64> Debug for Zmod<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Zmod<{}>({})", M, self.value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bigint::Integer, Cassini, FibFinder};
+
+    /// Checks every operation against u128 arithmetic for a few values.
+    fn check_ops<const M: u64>() {
+        let m = M as u128;
+        let values = [0, 1, 2, 3, 12345, M / 2, M - 1, u64::MAX];
+        for x in values {
+            assert_eq!(Zmod::<M>::new(x).value() as u128, x as u128 % m);
+            for y in values {
+                let (zx, zy) = (Zmod::<M>::new(x), Zmod::<M>::new(y));
+                let (x, y) = (x as u128 % m, y as u128 % m);
+                assert_eq!((zx + zy).value() as u128, (x + y) % m);
+                assert_eq!((zx - zy).value() as u128, (x + m - y) % m);
+                assert_eq!((zx * zy).value() as u128, x * y % m);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ops() {
+        check_ops::<1>();
+        check_ops::<2>();
+        check_ops::<7>();
+        check_ops::<1_000_000_007>();
+        check_ops::<{ 1 << 40 }>();
+        check_ops::<{ u64::MAX }>();
+        check_ops::<{ u64::MAX - 1 }>();
+        // the largest prime below 2^64
+        check_ops::<18446744073709551557>();
+    }
+
+    #[test]
+    fn test_inverse() {
+        for x in 1..97 {
+            let x = Zmod::<97>::new(x);
+            assert_eq!(x * x.inverse().unwrap(), Zmod::new(1));
+        }
+        assert_eq!(Zmod::<97>::new(0).inverse(), None);
+
+        assert_eq!(Zmod::<10>::new(3).inverse(), Some(Zmod::new(7)));
+        assert_eq!(Zmod::<10>::new(4).inverse(), None);
+        let x = Zmod::<{ u64::MAX }>::new(2);
+        assert_eq!(x * x.inverse().unwrap(), Zmod::new(1));
+    }
+
+    #[test]
+    fn test_fib_ring() {
+        const P: u64 = 18446744073709551557;
+        let mut alg = Cassini::default();
+        let p = Integer::from(P);
+        for n in [0, 1, 2, 12, 100, 1000, 10_000, u64::MAX] {
+            let f_n = alg.fib_ring::<Zmod<P>>(n);
+            assert_eq!(f_n.value(), alg.fib_mod(n, &p));
+            assert_eq!(
+                alg.fib_ring::<Zmod<1024>>(n).value(),
+                alg.fib_mod(n, &1024.into())
+            );
+        }
+    }
+}